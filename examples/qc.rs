@@ -1,9 +1,8 @@
-use font::{Font, OpenTypeFont};
 use pathfinder_export::{Export, FileFormat};
 use pathfinder_geometry::{rect::RectF, vector::vec2f};
 use pathfinder_renderer::scene::Scene;
 use rex::{
-    font::FontContext,
+    font::{load_math_font, Face, FontContext},
     layout::{engine, Grid, Layout, LayoutSettings, Style},
     parser::parse,
     render::{Renderer, SceneWrapper},
@@ -37,14 +36,9 @@ fn main() {
         .filter_map(|entry| {
             fs::read(entry.path())
                 .ok()
-                .and_then(|data| {
-                    font::parse(&data)
-                        .ok()
-                        .and_then(|f| f.downcast_box::<OpenTypeFont>().ok())
-                })
+                .and_then(|data| load_math_font(&data, Face::Index(0)).ok())
                 .map(|font| (font, entry.path()))
         })
-        .filter(|(font, path)| font.math.is_some())
         .collect();
 
     let mut grid = Grid::new();
@@ -227,3 +227,13 @@ pub enum Unit {
     Em(f64),
     Px(f64),
 }
+
+impl Neg for Unit {
+    type Output = Unit;
+    fn neg(self) -> Unit {
+        match self {
+            Unit::Em(em) => Unit::Em(-em),
+            Unit::Px(px) => Unit::Px(-px),
+        }
+    }
+}
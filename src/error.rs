@@ -15,6 +15,11 @@ pub enum LayoutError {
 pub enum FontError {
     MissingGlyphCodepoint(char),
     MissingGlyphGID(u16),
+    InvalidFont,
+    MissingMathTable,
+    MissingFace(usize),
+    MissingFaceName(String),
+    VariableFont,
 }
 impl From<FontError> for LayoutError {
     fn from(e: FontError) -> Self {
@@ -28,6 +33,8 @@ pub enum ParseError<'a> {
     UnrecognizedSymbol(char),
     UnrecognizedDimension,
     UnrecognizedColor(&'a str),
+    UnrecognizedPosition(&'a str),
+    UnrecognizedMathVersion(&'a str),
 
     ExpectedMathField(Token<'a>),
     ExpectedTokenFound(Token<'a>, Token<'a>),
@@ -47,6 +54,7 @@ pub enum ParseError<'a> {
     FailedToParse(Token<'a>),
     ExcessiveSubscripts,
     ExcessiveSuperscripts,
+    ScriptAfterColorDefinition,
 
     UnexpectedEof(Token<'a>),
 
@@ -74,6 +82,11 @@ impl fmt::Display for FontError {
         match *self {
             MissingGlyphCodepoint(cp) => write!(f, "missing glyph for codepoint'{}'", cp),
             MissingGlyphGID(gid) => write!(f, "missing glyph with gid {}", gid),
+            InvalidFont => write!(f, "failed to parse font"),
+            MissingMathTable => write!(f, "font has no MATH table"),
+            MissingFace(index) => write!(f, "font collection has no face {}", index),
+            MissingFaceName(ref name) => write!(f, "font collection has no face named `{}`", name),
+            VariableFont => write!(f, "variable fonts are not supported"),
         }
     }
 }
@@ -86,6 +99,7 @@ impl<'a> fmt::Display for ParseError<'a> {
             FailedToParse(ref tok) => write!(f, "failed to parse `{}`", tok),
             ExcessiveSubscripts => write!(f, "an excessive number of subscripts"),
             ExcessiveSuperscripts => write!(f, "excessive number of superscripts"),
+            ScriptAfterColorDefinition => write!(f, "a color definition cannot have scripts"),
             LimitsMustFollowOperator => write!(f, "limit commands must follow an operator"),
             ExpectedMathField(ref field) => write!(f, "expected math field, found `{}`", field),
             MissingSymbolAfterDelimiter => write!(f, "missing symbol following delimiter"),
@@ -119,6 +133,12 @@ impl<'a> fmt::Display for ParseError<'a> {
             ),
             UnrecognizedDimension => write!(f, "failed to parse dimension"),
             UnrecognizedColor(ref color) => write!(f, "failed to recognize the color '{}'", color),
+            UnrecognizedPosition(ref pos) => {
+                write!(f, "failed to recognize the position '{}'", pos)
+            }
+            UnrecognizedMathVersion(ref version) => {
+                write!(f, "failed to recognize the math version '{}'", version)
+            }
             Todo => write!(
                 f,
                 "failed with an unspecified error that has yet be implemented"
@@ -0,0 +1,175 @@
+use super::{style_symbol, Family, FontContext, Style, TextFont, VariantGlyph, Weight};
+use crate::functions::{get_command, COMMAND_NAMES, PARSER_COMMAND_NAMES};
+use crate::parser::symbols::Symbol;
+use font::GlyphId;
+
+const FAMILIES: [Family; 7] = [
+    Family::Normal,
+    Family::Roman,
+    Family::Script,
+    Family::Fraktur,
+    Family::SansSerif,
+    Family::Blackboard,
+    Family::Monospace,
+];
+
+const WEIGHTS: [Weight; 4] = [
+    Weight::None,
+    Weight::Italic,
+    Weight::Bold,
+    Weight::BoldItalic,
+];
+
+/// The symbols and commands which can be rendered with a font, as found with
+/// `FontContext::coverage`.  Names are sorted, and given without the leading
+/// backslash.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Coverage {
+    pub symbols: Vec<&'static str>,
+    pub missing_symbols: Vec<&'static str>,
+    pub commands: Vec<&'static str>,
+    pub missing_commands: Vec<&'static str>,
+    /// The alphabets with a glyph for every Latin letter.
+    pub alphabets: Vec<(Family, Weight)>,
+}
+
+impl<'f> FontContext<'f> {
+    /// Checks every symbol and command name against the math font and its
+    /// fallbacks.  The bold math font can be checked on its own.
+    pub fn coverage(&self) -> Coverage {
+        self.coverage_in(None)
+    }
+
+    /// As `coverage`, with the names of operators such as `\sin` checked
+    /// against the text font, as laid out with `LayoutSettings::with_text_font`.
+    pub fn coverage_with_text_font(&self, text_font: &TextFont) -> Coverage {
+        self.coverage_in(Some(text_font))
+    }
+
+    fn coverage_in(&self, text_font: Option<&TextFont>) -> Coverage {
+        let mut coverage = Coverage {
+            symbols: Vec::new(),
+            missing_symbols: Vec::new(),
+            commands: Vec::new(),
+            missing_commands: Vec::new(),
+            alphabets: Vec::new(),
+        };
+
+        let mut names: Vec<&'static str> = Symbol::names().collect();
+        names.sort_unstable();
+        names.dedup();
+        for name in names {
+            if self.supports_symbol(name) {
+                coverage.symbols.push(name);
+            } else {
+                coverage.missing_symbols.push(name);
+            }
+        }
+
+        let mut names = COMMAND_NAMES.to_vec();
+        names.extend_from_slice(PARSER_COMMAND_NAMES);
+        names.sort_unstable();
+        names.dedup();
+        for name in names {
+            if self.supports_command_in(name, text_font) {
+                coverage.commands.push(name);
+            } else {
+                coverage.missing_commands.push(name);
+            }
+        }
+
+        for &family in FAMILIES.iter() {
+            for &weight in WEIGHTS.iter() {
+                if self.supports_alphabet(family, weight) {
+                    coverage.alphabets.push((family, weight));
+                }
+            }
+        }
+
+        coverage
+    }
+
+    /// Returns true if the symbol `\name` can be rendered, including its
+    /// size variants and assemblies.
+    pub fn supports_symbol(&self, name: &str) -> bool {
+        match Symbol::from_name(name) {
+            Some(sym) => self.supports_codepoint(sym.codepoint),
+            None => false,
+        }
+    }
+
+    /// Returns true if the command `\name` can be rendered, not counting the
+    /// symbols given in its arguments.  Operator names are checked against the
+    /// math font, as they are laid out when no text font is given.
+    pub fn supports_command(&self, name: &str) -> bool {
+        self.supports_command_in(name, None)
+    }
+
+    /// As `supports_command`, with operator names checked against the text font.
+    pub fn supports_command_with_text_font(&self, name: &str, text_font: &TextFont) -> bool {
+        self.supports_command_in(name, Some(text_font))
+    }
+
+    fn supports_command_in(&self, name: &str, text_font: Option<&TextFont>) -> bool {
+        if PARSER_COMMAND_NAMES.contains(&name) {
+            return true;
+        }
+
+        match get_command(name) {
+            Some(command) => {
+                command
+                    .codepoints()
+                    .into_iter()
+                    .all(|codepoint| self.supports_codepoint(codepoint))
+                    && command
+                        .text_codepoints()
+                        .into_iter()
+                        .all(|codepoint| self.supports_text(codepoint, text_font))
+            }
+            None => false,
+        }
+    }
+
+    fn supports_alphabet(&self, family: Family, weight: Weight) -> bool {
+        let style = Style::new().with_family(family).with_weight(weight);
+        ('a'..='z')
+            .chain('A'..='Z')
+            .all(|c| self.supports_codepoint(style_symbol(c, style)))
+    }
+
+    /// Codepoints missing from the math font may still be found in one of the
+    /// fallback fonts, but have no variants there.
+    fn supports_codepoint(&self, codepoint: char) -> bool {
+        use font::Font;
+        let gid = match self.font.gid_for_codepoint(codepoint as u32) {
+            Some(GlyphId(gid)) => gid as u16,
+            None => return self.glyph(codepoint).is_ok(),
+        };
+        if self.glyph_from_gid(gid).is_err() {
+            return false;
+        }
+
+        // The smallest and largest sizes find the first and last variants, and
+        // any assembly of parts.  The `MATH` table is read directly, so that the
+        // variant cache only holds the sizes asked for by layouts.
+        [0, u32::MAX].iter().all(|&size| {
+            self.supports_variant(&self.math.variants.vert_variant(gid, size))
+                && self.supports_variant(&self.math.variants.horz_variant(gid, size))
+        })
+    }
+
+    /// Running text is set in the text font, and codepoints missing from it are
+    /// taken from the math font and its fallbacks.
+    fn supports_text(&self, codepoint: char, text_font: Option<&TextFont>) -> bool {
+        text_font.is_some_and(|font| font.gid(codepoint).is_some()) || self.glyph(codepoint).is_ok()
+    }
+
+    fn supports_variant(&self, variant: &VariantGlyph) -> bool {
+        match *variant {
+            VariantGlyph::Replacement(gid) => self.glyph_from_gid(gid).is_ok(),
+            VariantGlyph::Constructable(_, ref parts) => parts
+                .iter()
+                .all(|part| self.glyph_from_gid(part.gid).is_ok()),
+        }
+    }
+}
@@ -60,6 +60,38 @@ pub fn subscript_kern(base: &Glyph, script: &Glyph, shift: Length<Font>) -> Leng
     min(value1, value2)
 }
 
+/// The mirror image of `superscript_kern`, for a superscript placed to the
+/// left of the base.  This uses the top left corner of the base and the
+/// bottom right corner of the script.
+pub fn presuperscript_kern(base: &Glyph, script: &Glyph, shift: Length<Font>) -> Length<Font> {
+    let base_height = base.bbox.3;
+    let script_depth = script.bbox.1 + shift;
+
+    let value1 = kern_from(base, base_height, Corner::TopLeft)
+        + kern_from(script, base_height, Corner::BottomRight);
+
+    let value2 = kern_from(base, script_depth, Corner::TopLeft)
+        + kern_from(script, script_depth, Corner::BottomRight);
+
+    max(value1, value2)
+}
+
+/// The mirror image of `subscript_kern`, for a subscript placed to the
+/// left of the base.  This uses the bottom left corner of the base and the
+/// top right corner of the script.
+pub fn presubscript_kern(base: &Glyph, script: &Glyph, shift: Length<Font>) -> Length<Font> {
+    let base_depth = base.bbox.1;
+    let script_height = script.bbox.3 - shift;
+
+    let value1 = kern_from(base, base_depth, Corner::BottomLeft)
+        + kern_from(script, base_depth, Corner::TopRight);
+
+    let value2 = kern_from(base, script_height, Corner::BottomLeft)
+        + kern_from(script, script_height, Corner::TopRight);
+
+    min(value1, value2)
+}
+
 fn kern_from(glyph: &Glyph, height: Length<Font>, side: Corner) -> Length<Font> {
     let math = glyph.font.math.as_ref().unwrap();
     let record = match math.glyph_info.kern_info.entries.get(&glyph.gid) {
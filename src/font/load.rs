@@ -0,0 +1,251 @@
+use super::MathFont;
+use crate::error::FontError;
+use font::OpenTypeFont;
+
+/// Selects a face from a font collection, such as a `.ttc` file.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Face<'a> {
+    Index(usize),
+    /// Matches the family, full or PostScript name of the face.
+    Name(&'a str),
+}
+
+const NAME_FAMILY: u16 = 1;
+const NAME_FULL: u16 = 4;
+const NAME_POSTSCRIPT: u16 = 6;
+
+/// Parses a math font from OpenType, TrueType, WOFF or WOFF2 data, or from a
+/// face of a font collection.  A single font only has the face at index 0,
+/// and WOFF and WOFF2 fonts can only be selected by index.
+///
+/// Variable fonts are rejected with `FontError::VariableFont`, as neither
+/// named instances nor axis values can be selected.
+pub fn load_math_font(data: &[u8], face: Face) -> Result<MathFont, FontError> {
+    let offset = match face {
+        Face::Index(index) => face_offset(data, index)?,
+        Face::Name(name) => find_face(data, name)?,
+    };
+    let face;
+    let data = if is_collection(data) {
+        face = extract_face(data, offset)?;
+        &face[..]
+    } else {
+        data
+    };
+    if is_variable(data)? {
+        return Err(FontError::VariableFont);
+    }
+
+    let font = font::parse(data)
+        .map_err(|_| FontError::InvalidFont)?
+        .downcast_box::<OpenTypeFont>()
+        .map_err(|_| FontError::InvalidFont)?;
+    if font.math.is_none() {
+        return Err(FontError::MissingMathTable);
+    }
+    Ok(*font)
+}
+
+/// The full name of every face in a font collection, or of a single font.
+/// Faces without a name are given as an empty string.
+pub fn face_names(data: &[u8]) -> Result<Vec<String>, FontError> {
+    face_offsets(data)?
+        .into_iter()
+        .map(|offset| {
+            let names = face_names_at(data, offset)?;
+            let full_name = names.into_iter().find(|&(id, _)| id == NAME_FULL);
+            Ok(full_name.map(|(_, name)| name).unwrap_or_default())
+        })
+        .collect()
+}
+
+fn is_collection(data: &[u8]) -> bool {
+    data.starts_with(b"ttcf")
+}
+
+fn read_u16(data: &[u8], pos: usize) -> Result<u16, FontError> {
+    match data.get(pos..pos + 2) {
+        Some(bytes) => Ok(u16::from_be_bytes([bytes[0], bytes[1]])),
+        None => Err(FontError::InvalidFont),
+    }
+}
+
+fn read_u32(data: &[u8], pos: usize) -> Result<u32, FontError> {
+    match data.get(pos..pos + 4) {
+        Some(bytes) => Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])),
+        None => Err(FontError::InvalidFont),
+    }
+}
+
+/// The offsets of the table directories of each face.
+fn face_offsets(data: &[u8]) -> Result<Vec<usize>, FontError> {
+    if !is_collection(data) {
+        return Ok(vec![0]);
+    }
+
+    let count = read_u32(data, 8)? as usize;
+    (0..count)
+        .map(|index| Ok(read_u32(data, 12 + 4 * index)? as usize))
+        .collect()
+}
+
+fn face_offset(data: &[u8], index: usize) -> Result<usize, FontError> {
+    match face_offsets(data)?.get(index) {
+        Some(&offset) => Ok(offset),
+        None => Err(FontError::MissingFace(index)),
+    }
+}
+
+fn find_face(data: &[u8], name: &str) -> Result<usize, FontError> {
+    for offset in face_offsets(data)? {
+        if has_name(data, offset, name)? {
+            return Ok(offset);
+        }
+    }
+    Err(FontError::MissingFaceName(name.to_string()))
+}
+
+/// The position of a table in the face with the table directory at `offset`.
+fn find_table(data: &[u8], offset: usize, tag: &[u8; 4]) -> Result<Option<usize>, FontError> {
+    let num_tables = read_u16(data, offset + 4)? as usize;
+    for i in 0..num_tables {
+        let record = offset + 12 + 16 * i;
+        if data.get(record..record + 4) == Some(&tag[..]) {
+            return Ok(Some(read_u32(data, record + 8)? as usize));
+        }
+    }
+    Ok(None)
+}
+
+/// Returns true if a single font, in any of the formats, has an `fvar` table.
+fn is_variable(data: &[u8]) -> Result<bool, FontError> {
+    if data.starts_with(b"wOFF") {
+        let num_tables = read_u16(data, 12)? as usize;
+        let tag = |i: usize| data.get(44 + 20 * i..48 + 20 * i);
+        return Ok((0..num_tables).any(|i| tag(i) == Some(&b"fvar"[..])));
+    }
+    if !data.starts_with(b"wOF2") {
+        return Ok(find_table(data, 0, b"fvar")?.is_some());
+    }
+
+    // Each entry of the WOFF2 table directory has a flags byte, the tag unless
+    // it is one of the known tags, and the lengths of the table.  Only the
+    // known tags which matter here are named.
+    let num_tables = read_u16(data, 12)?;
+    let mut pos = 48;
+    for _ in 0..num_tables {
+        let flags = *data.get(pos).ok_or(FontError::InvalidFont)?;
+        pos += 1;
+        let tag: &[u8] = match flags & 63 {
+            10 => b"glyf",
+            11 => b"loca",
+            47 => b"fvar",
+            63 => {
+                pos += 4;
+                data.get(pos - 4..pos).ok_or(FontError::InvalidFont)?
+            }
+            _ => b"",
+        };
+        if tag == b"fvar" {
+            return Ok(true);
+        }
+
+        // `glyf` and `loca` are transformed by version 0, and other tables by
+        // any other version.  Transformed tables also give their new length.
+        let transformed = (flags >> 6 == 0) == matches!(tag, b"glyf" | b"loca");
+        skip_base128(data, &mut pos)?;
+        if transformed {
+            skip_base128(data, &mut pos)?;
+        }
+    }
+    Ok(false)
+}
+
+fn skip_base128(data: &[u8], pos: &mut usize) -> Result<(), FontError> {
+    for _ in 0..5 {
+        let byte = *data.get(*pos).ok_or(FontError::InvalidFont)?;
+        *pos += 1;
+        if byte & 0x80 == 0 {
+            return Ok(());
+        }
+    }
+    Err(FontError::InvalidFont)
+}
+
+/// The family, full and PostScript names of the face with the table
+/// directory at `offset`, with their name IDs.  The names of WOFF and WOFF2
+/// data are compressed, and are not read.
+fn face_names_at(data: &[u8], offset: usize) -> Result<Vec<(u16, String)>, FontError> {
+    if data.starts_with(b"wOFF") || data.starts_with(b"wOF2") {
+        return Ok(Vec::new());
+    }
+    let table = match find_table(data, offset, b"name")? {
+        Some(table) => table,
+        None => return Ok(Vec::new()),
+    };
+
+    let count = read_u16(data, table + 2)? as usize;
+    let strings = table + read_u16(data, table + 4)? as usize;
+    let mut names = Vec::new();
+    for i in 0..count {
+        let record = table + 6 + 12 * i;
+        let platform = read_u16(data, record)?;
+        let name_id = read_u16(data, record + 6)?;
+        if !matches!(name_id, NAME_FAMILY | NAME_FULL | NAME_POSTSCRIPT) {
+            continue;
+        }
+
+        let len = read_u16(data, record + 8)? as usize;
+        let start = strings + read_u16(data, record + 10)? as usize;
+        let bytes = data.get(start..start + len).ok_or(FontError::InvalidFont)?;
+        let name = match platform {
+            // Unicode and Windows names are UTF-16BE.
+            0 | 3 => {
+                let units: Vec<u16> = bytes
+                    .chunks_exact(2)
+                    .map(|unit| u16::from_be_bytes([unit[0], unit[1]]))
+                    .collect();
+                String::from_utf16_lossy(&units)
+            }
+            // Macintosh names are Mac Roman, which agrees with ASCII.
+            1 => bytes.iter().map(|&b| b as char).collect(),
+            _ => continue,
+        };
+        names.push((name_id, name));
+    }
+    Ok(names)
+}
+
+fn has_name(data: &[u8], offset: usize, name: &str) -> Result<bool, FontError> {
+    Ok(face_names_at(data, offset)?
+        .iter()
+        .any(|(_, face_name)| face_name == name))
+}
+
+/// Copies a face out of a collection into a single font.  The tables of a
+/// face are found at offsets from the start of the collection, so they are
+/// moved after the table directory of the new font.
+fn extract_face(data: &[u8], offset: usize) -> Result<Vec<u8>, FontError> {
+    let num_tables = read_u16(data, offset + 4)? as usize;
+    let dir_len = 12 + 16 * num_tables;
+    let dir = data
+        .get(offset..offset + dir_len)
+        .ok_or(FontError::InvalidFont)?;
+
+    let mut font = dir.to_vec();
+    for i in 0..num_tables {
+        let record = 12 + 16 * i;
+        let start = read_u32(dir, record + 8)? as usize;
+        let len = read_u32(dir, record + 12)? as usize;
+        let table = data.get(start..start + len).ok_or(FontError::InvalidFont)?;
+
+        // Tables are aligned to four bytes.
+        while font.len() % 4 != 0 {
+            font.push(0);
+        }
+        let new_start = font.len() as u32;
+        font[record + 8..record + 12].copy_from_slice(&new_start.to_be_bytes());
+        font.extend_from_slice(table);
+    }
+    Ok(font)
+}
@@ -1,8 +1,12 @@
+mod coverage;
 pub mod kerning;
+mod load;
 mod style;
 //mod unit;
 
-pub use style::style_symbol;
+pub use coverage::Coverage;
+pub use load::{face_names, load_math_font, Face};
+pub use style::{dotless_codepoint, is_dotted, is_math_alphanumeric, is_math_italic, style_symbol};
 pub use unicode_math::AtomType;
 
 pub use font::opentype::math::{assembly::Direction, assembly::VariantGlyph, MathConstants};
@@ -13,70 +17,252 @@ use font::{
 
 use crate::dimensions::*;
 use crate::error::FontError;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::ops::Deref;
+use std::sync::{Arc, PoisonError, RwLock};
 
 pub type MathFont = OpenTypeFont;
 
+/// A font which is either borrowed, or shared through an `Arc`.  Contexts and
+/// layouts built only from shared fonts are `'static`, so that they can be
+/// kept in caches or sent to other threads.
+#[derive(Clone)]
+pub enum FontRef<'f> {
+    Borrowed(&'f MathFont),
+    Shared(Arc<MathFont>),
+}
+impl<'f> FontRef<'f> {
+    /// Returns true if both refer to the same font.
+    pub fn ptr_eq(&self, other: &FontRef) -> bool {
+        std::ptr::eq::<MathFont>(&**self, &**other)
+    }
+}
+impl<'f> Deref for FontRef<'f> {
+    type Target = MathFont;
+    fn deref(&self) -> &MathFont {
+        match *self {
+            FontRef::Borrowed(font) => font,
+            FontRef::Shared(ref font) => font,
+        }
+    }
+}
+impl<'f> From<&'f MathFont> for FontRef<'f> {
+    fn from(font: &'f MathFont) -> Self {
+        FontRef::Borrowed(font)
+    }
+}
+impl From<Arc<MathFont>> for FontRef<'static> {
+    fn from(font: Arc<MathFont>) -> Self {
+        FontRef::Shared(font)
+    }
+}
+
+/// The `MATH` table of a math font, which is borrowed or shared along with
+/// the font.  It dereferences to the table, as the `&MathHeader` it replaces.
+#[derive(Clone)]
+pub struct MathRef<'f>(FontRef<'f>);
+impl<'f> MathRef<'f> {
+    fn new(font: FontRef<'f>) -> Self {
+        assert!(font.math.is_some(), "no MATH tables");
+        MathRef(font)
+    }
+}
+impl<'f> Deref for MathRef<'f> {
+    type Target = MathHeader;
+    fn deref(&self) -> &MathHeader {
+        self.0.math.as_ref().expect("no MATH tables")
+    }
+}
+
 #[derive(Clone)]
 pub struct FontContext<'f> {
-    pub font: &'f MathFont,
-    pub math: &'f MathHeader,
+    pub font: FontRef<'f>,
+    pub math: MathRef<'f>,
     pub constants: Constants,
     pub units_per_em: Scale<Font, Em>,
+    /// Fonts searched in order for codepoints missing from the math font.
+    pub fallbacks: Vec<FontRef<'f>>,
+    /// The math font used for `\mathversion{bold}` and `\boldmath`.
+    pub bold: Option<Box<FontContext<'f>>>,
+    cache: Arc<GlyphCache<'f>>,
+}
+
+/// Glyph metrics and variants, which are costly to compute from the outlines
+/// and tables of a font.  The cache is shared by clones of a `FontContext`,
+/// including those on other threads.
+#[derive(Default)]
+struct GlyphCache<'f> {
+    glyphs: RwLock<HashMap<u16, Glyph<'f>>>,
+    /// Glyphs from the fallback and text fonts, keyed by the address of the
+    /// font.  The cached glyph holds a reference to its font, so the address is
+    /// not reused by another font while the entry exists.
+    other_glyphs: RwLock<HashMap<(usize, u16), Glyph<'f>>>,
+    vert_variants: RwLock<HashMap<(u16, u32), VariantGlyph>>,
+    horz_variants: RwLock<HashMap<(u16, u32), VariantGlyph>>,
 }
+
+/// The number of variants cached for each direction.  Variants are keyed by
+/// the requested size as well as the glyph, so a long-lived context would
+/// otherwise keep one for every size it has been asked for.  The cache is
+/// emptied when it is full.
+const VARIANT_CACHE_LIMIT: usize = 1024;
+
+fn cached<K: Eq + Hash, V: Clone>(
+    map: &RwLock<HashMap<K, V>>,
+    key: K,
+    limit: usize,
+    compute: impl FnOnce() -> Result<V, FontError>,
+) -> Result<V, FontError> {
+    if let Some(value) = map.read().unwrap_or_else(PoisonError::into_inner).get(&key) {
+        return Ok(value.clone());
+    }
+
+    let value = compute()?;
+    let mut map = map.write().unwrap_or_else(PoisonError::into_inner);
+    if map.len() >= limit {
+        map.clear();
+    }
+    map.insert(key, value.clone());
+    Ok(value)
+}
+impl FontContext<'static> {
+    /// A context which shares ownership of the math font.  Layouts from this
+    /// context are `'static`, provided any fallback, bold or text fonts are
+    /// shared as well.
+    pub fn shared(font: Arc<MathFont>) -> Self {
+        FontContext::from_ref(FontRef::Shared(font))
+    }
+}
+
 impl<'f> FontContext<'f> {
     pub fn glyph(&self, codepoint: char) -> Result<Glyph<'f>, FontError> {
         use font::Font;
-        let gid = self
-            .font
-            .gid_for_codepoint(codepoint as u32)
-            .ok_or(FontError::MissingGlyphCodepoint(codepoint))?;
-        self.glyph_from_gid(gid.0 as u16)
+        if let Some(GlyphId(gid)) = self.font.gid_for_codepoint(codepoint as u32) {
+            return self.glyph_from_gid(gid as u16);
+        }
+
+        for font in &self.fallbacks {
+            if let Some(GlyphId(gid)) = font.gid_for_codepoint(codepoint as u32) {
+                return self.other_glyph(font, gid as u16);
+            }
+        }
+
+        Err(FontError::MissingGlyphCodepoint(codepoint))
     }
-    pub fn glyph_from_gid(&self, gid: u16) -> Result<Glyph<'f>, FontError> {
+
+    /// A glyph from a font other than the math font.  Metrics are converted
+    /// into the units of the math font, so that the glyph is laid out at the
+    /// same size.
+    fn other_glyph(&self, font: &FontRef<'f>, gid: u16) -> Result<Glyph<'f>, FontError> {
         use font::Font;
-        let font = self.font;
-        let hmetrics = font
-            .glyph_metrics(gid)
-            .ok_or(FontError::MissingGlyphGID(gid))?;
-        let italics = self
-            .math
-            .glyph_info
-            .italics_correction_info
-            .get(gid)
-            .map(|info| info.value)
-            .unwrap_or_default();
-        let attachment = self
-            .math
-            .glyph_info
-            .top_accent_attachment
-            .get(gid)
-            .map(|info| info.value)
-            .unwrap_or_default();
-        let glyph = font
-            .glyph(GlyphId(gid as u32))
-            .ok_or(FontError::MissingGlyphGID(gid))?;
-        let bbox = glyph.path.bounds();
-        let ll = bbox.lower_left();
-        let ur = bbox.upper_right();
+        let key = (&**font as *const MathFont as usize, gid);
+        cached(&self.cache.other_glyphs, key, usize::MAX, || {
+            let font_units_to_em = Scale::new(font.font_matrix().matrix.m11() as f64, Em, Font);
+            outline_glyph(font.clone(), gid, self.units_per_em * font_units_to_em)
+        })
+    }
 
-        Ok(Glyph {
-            gid,
-            font: self.font,
-            advance: Length::new(hmetrics.advance, Font),
-            lsb: Length::new(hmetrics.lsb, Font),
-            italics: Length::new(italics, Font),
-            attachment: Length::new(attachment, Font),
-            bbox: (
-                Length::new(ll.x(), Font),
-                Length::new(ur.y(), Font),
-                Length::new(ur.x(), Font),
-                Length::new(ll.y(), Font),
-            ),
+    /// Adds a font which is searched for codepoints missing from the math font,
+    /// and from any fallback added before it.
+    pub fn with_fallback(mut self, font: impl Into<FontRef<'f>>) -> Self {
+        let font = font.into();
+        self.fallbacks.push(font.clone());
+        self.bold = self.bold.map(|bold| Box::new(bold.with_fallback(font)));
+        self
+    }
+
+    /// Replaces some of the constants read from the `MATH` table of the math
+    /// font.  The bold math font keeps its own constants.
+    pub fn with_constants(mut self, overrides: &ConstantsOverride) -> Self {
+        overrides.apply(&mut self.constants);
+        self
+    }
+
+    /// Sets the bold math font, whose constants and variants are used for
+    /// everything laid out in the bold math version.  It shares the fallback
+    /// fonts of this context.
+    pub fn with_bold(mut self, font: impl Into<FontRef<'f>>) -> Self {
+        let bold = FontContext {
+            fallbacks: self.fallbacks.clone(),
+            ..FontContext::from_ref(font.into())
+        };
+        self.bold = Some(Box::new(bold));
+        self
+    }
+
+    /// Returns true if the glyph was taken from the math font, rather than
+    /// from one of the fallback fonts.
+    pub fn is_math_glyph(&self, glyph: &Glyph) -> bool {
+        self.font.ptr_eq(&glyph.font)
+    }
+    /// The alternate of a glyph for use in scripts, as found with the `ssty`
+    /// feature in the `GSUB` table.  The `level` is 1 for scripts and 2 for
+    /// scriptscripts.  When a font provides fewer alternates than the level,
+    /// the last alternate is used.
+    pub fn script_alternate(&self, gid: u16, level: u8) -> Option<u16> {
+        let alternates = self.alternates(b"ssty", gid)?;
+        let GlyphId(alternate) = alternates
+            .get(level as usize - 1)
+            .or_else(|| alternates.last())?;
+        Some(*alternate as u16)
+    }
+
+    /// The substitute of a glyph for a `GSUB` feature with a single
+    /// substitution, such as `dtls` (dotless forms) or `flac` (flattened
+    /// accents).
+    pub fn substitute(&self, feature: &[u8; 4], gid: u16) -> Option<u16> {
+        let GlyphId(substitute) = self.alternates(feature, gid)?.first()?;
+        Some(*substitute as u16)
+    }
+
+    fn alternates(&self, feature: &[u8; 4], gid: u16) -> Option<&[GlyphId]> {
+        let gsub = self.font.gsub.as_ref()?;
+        gsub.alternates(feature, GlyphId(gid as u32))
+    }
+
+    /// The kerning between a pair of glyphs, as found in the `GPOS` table.
+    pub fn kerning(&self, left: u16, right: u16) -> Length<Font> {
+        use font::Font;
+        let kern = self
+            .font
+            .kerning(GlyphId(left as u32), GlyphId(right as u32));
+        Length::new(kern, Font)
+    }
+
+    pub fn glyph_from_gid(&self, gid: u16) -> Result<Glyph<'f>, FontError> {
+        cached(&self.cache.glyphs, gid, usize::MAX, || {
+            let glyph = outline_glyph(self.font.clone(), gid, Scale::new(1.0, Font, Font))?;
+            let italics = self
+                .math
+                .glyph_info
+                .italics_correction_info
+                .get(gid)
+                .map(|info| info.value)
+                .unwrap_or_default();
+            let attachment = self
+                .math
+                .glyph_info
+                .top_accent_attachment
+                .get(gid)
+                .map(|info| info.value)
+                .unwrap_or_default();
+
+            Ok(Glyph {
+                italics: Length::new(italics, Font),
+                attachment: Length::new(attachment, Font),
+                ..glyph
+            })
         })
     }
+    /// A context which borrows the math font.
     pub fn new(font: &'f MathFont) -> Self {
+        FontContext::from_ref(FontRef::Borrowed(font))
+    }
+
+    fn from_ref(font: FontRef<'f>) -> Self {
         use font::Font;
-        let math = font.math.as_ref().expect("no MATH tables");
+        let math = MathRef::new(font.clone());
         let font_units_to_em = Scale::new(font.font_matrix().matrix.m11() as f64, Em, Font);
         let units_per_em = font_units_to_em.inv();
         let constants = Constants::new(&math.constants, font_units_to_em);
@@ -86,6 +272,9 @@ impl<'f> FontContext<'f> {
             math,
             units_per_em,
             constants,
+            fallbacks: Vec::new(),
+            bold: None,
+            cache: Arc::default(),
         }
     }
     pub fn vert_variant(
@@ -98,10 +287,11 @@ impl<'f> FontContext<'f> {
             .font
             .gid_for_codepoint(codepoint as u32)
             .ok_or(FontError::MissingGlyphCodepoint(codepoint))?;
-        Ok(self
-            .math
-            .variants
-            .vert_variant(gid as u16, (height / Font) as u32))
+        let height = (height / Font) as u32;
+        let key = (gid as u16, height);
+        cached(&self.cache.vert_variants, key, VARIANT_CACHE_LIMIT, || {
+            Ok(self.math.variants.vert_variant(gid as u16, height))
+        })
     }
     pub fn horz_variant(
         &self,
@@ -113,10 +303,70 @@ impl<'f> FontContext<'f> {
             .font
             .gid_for_codepoint(codepoint as u32)
             .ok_or(FontError::MissingGlyphCodepoint(codepoint))?;
-        Ok(self
-            .math
-            .variants
-            .horz_variant(gid as u16, (width / Font) as u32))
+        let width = (width / Font) as u32;
+        let key = (gid as u16, width);
+        cached(&self.cache.horz_variants, key, VARIANT_CACHE_LIMIT, || {
+            Ok(self.math.variants.horz_variant(gid as u16, width))
+        })
+    }
+}
+
+/// A font for running text in `\text` and operator names, such as the body
+/// typeface of a document.  Unlike `FontContext`, it needs no `MATH` table.
+#[derive(Clone)]
+pub struct TextFont<'f> {
+    pub font: FontRef<'f>,
+    pub units_per_em: Scale<Font, Em>,
+}
+impl<'f> TextFont<'f> {
+    pub fn new(font: impl Into<FontRef<'f>>) -> Self {
+        use font::Font;
+        let font = font.into();
+        let font_units_to_em = Scale::new(font.font_matrix().matrix.m11() as f64, Em, Font);
+        TextFont {
+            font,
+            units_per_em: font_units_to_em.inv(),
+        }
+    }
+
+    pub fn gid(&self, codepoint: char) -> Option<u16> {
+        use font::Font;
+        let GlyphId(gid) = self.font.gid_for_codepoint(codepoint as u32)?;
+        Some(gid as u16)
+    }
+
+    /// The glyph with metrics converted into the units of the math font of `ctx`.
+    /// Glyphs are cached by `ctx`.  Text set in the math font shares its glyphs,
+    /// without the italic corrections and accent attachments of the `MATH` table.
+    pub fn glyph(&self, ctx: &FontContext<'f>, gid: u16) -> Result<Glyph<'f>, FontError> {
+        if !self.font.ptr_eq(&ctx.font) {
+            return ctx.other_glyph(&self.font, gid);
+        }
+        Ok(Glyph {
+            italics: Length::zero(),
+            attachment: Length::zero(),
+            ..ctx.glyph_from_gid(gid)?
+        })
+    }
+
+    /// The kerning between a pair of glyphs from the `GPOS` table, in the
+    /// units of the math font of `ctx`.
+    pub fn kerning(&self, ctx: &FontContext<'f>, left: u16, right: u16) -> Length<Font> {
+        use font::Font;
+        let kern = self
+            .font
+            .kerning(GlyphId(left as u32), GlyphId(right as u32));
+        Length::new(kern, Font) * (ctx.units_per_em * self.units_per_em.inv())
+    }
+
+    /// The standard ligature, found with the `liga` feature in the `GSUB`
+    /// table, which starts a sequence of glyphs.  Returns the ligature and
+    /// the number of glyphs it replaces.
+    pub fn ligature(&self, gids: &[u16]) -> Option<(u16, usize)> {
+        let gsub = self.font.gsub.as_ref()?;
+        let gids: Vec<GlyphId> = gids.iter().map(|&gid| GlyphId(gid as u32)).collect();
+        let (GlyphId(ligature), len) = gsub.ligature(b"liga", &gids)?;
+        Some((ligature as u16, len))
     }
 }
 
@@ -149,6 +399,7 @@ pub struct Constants {
 
     pub axis_height: Length<Em>,
     pub accent_base_height: Length<Em>,
+    pub flattened_accent_base_height: Length<Em>,
 
     pub delimited_sub_formula_min_height: Length<Em>,
     pub display_operator_min_height: Length<Em>,
@@ -177,7 +428,7 @@ impl Constants {
         let em = |v: f64| -> Length<Em> { Length::new(v, Font) * font_units_to_em };
 
         Constants {
-            subscript_shift_down: em(math.subscript_top_max.value.into()),
+            subscript_shift_down: em(math.subscript_shift_down.value.into()),
             subscript_top_max: em(math.subscript_top_max.value.into()),
             subscript_baseline_drop_min: em(math.subscript_baseline_drop_min.value.into()),
 
@@ -216,6 +467,7 @@ impl Constants {
 
             axis_height: em(math.axis_height.value.into()),
             accent_base_height: em(math.accent_base_height.value.into()),
+            flattened_accent_base_height: em(math.flattened_accent_base_height.value.into()),
 
             delimited_sub_formula_min_height: em(math.delimited_sub_formula_min_height.into()),
 
@@ -248,8 +500,133 @@ impl Constants {
     }
 }
 
+macro_rules! constants_override {
+    ($($field:ident: $ty:ty,)*) => {
+        /// Replacements for the constants read from the `MATH` table of a font,
+        /// applied with `FontContext::with_constants`.  Each method sets the
+        /// constant of the same name, and constants which are not set keep
+        /// the value from the font.
+        #[derive(Clone, Default)]
+        pub struct ConstantsOverride {
+            $($field: Option<$ty>,)*
+        }
+
+        impl ConstantsOverride {
+            pub fn new() -> Self {
+                Self::default()
+            }
+
+            $(
+                pub fn $field(self, $field: $ty) -> Self {
+                    ConstantsOverride {
+                        $field: Some($field),
+                        ..self
+                    }
+                }
+            )*
+
+            pub fn apply(&self, constants: &mut Constants) {
+                $(
+                    if let Some(value) = self.$field {
+                        constants.$field = value;
+                    }
+                )*
+            }
+        }
+    };
+}
+
+constants_override! {
+    subscript_shift_down: Length<Em>,
+    subscript_top_max: Length<Em>,
+    subscript_baseline_drop_min: Length<Em>,
+
+    superscript_baseline_drop_max: Length<Em>,
+    superscript_bottom_min: Length<Em>,
+    superscript_shift_up_cramped: Length<Em>,
+    superscript_shift_up: Length<Em>,
+    sub_superscript_gap_min: Length<Em>,
+
+    upper_limit_baseline_rise_min: Length<Em>,
+    upper_limit_gap_min: Length<Em>,
+    lower_limit_gap_min: Length<Em>,
+    lower_limit_baseline_drop_min: Length<Em>,
+
+    fraction_rule_thickness: Length<Em>,
+    fraction_numerator_display_style_shift_up: Length<Em>,
+    fraction_denominator_display_style_shift_down: Length<Em>,
+    fraction_num_display_style_gap_min: Length<Em>,
+    fraction_denom_display_style_gap_min: Length<Em>,
+    fraction_numerator_shift_up: Length<Em>,
+    fraction_denominator_shift_down: Length<Em>,
+    fraction_numerator_gap_min: Length<Em>,
+    fraction_denominator_gap_min: Length<Em>,
+
+    axis_height: Length<Em>,
+    accent_base_height: Length<Em>,
+    flattened_accent_base_height: Length<Em>,
+
+    delimited_sub_formula_min_height: Length<Em>,
+    display_operator_min_height: Length<Em>,
+
+    radical_display_style_vertical_gap: Length<Em>,
+    radical_vertical_gap: Length<Em>,
+    radical_rule_thickness: Length<Em>,
+    radical_extra_ascender: Length<Em>,
+
+    stack_display_style_gap_min: Length<Em>,
+    stack_top_display_style_shift_up: Length<Em>,
+    stack_top_shift_up: Length<Em>,
+    stack_bottom_shift_down: Length<Em>,
+    stack_gap_min: Length<Em>,
+
+    delimiter_factor: f64,
+    delimiter_short_fall: Length<Em>,
+    null_delimiter_space: Length<Em>,
+
+    script_percent_scale_down: f64,
+    script_script_percent_scale_down: f64,
+}
+
+/// The outline metrics of a glyph from any font, converted with `scale` into
+/// the units of the math font.  Italic correction and accent attachment only
+/// exist in the `MATH` table, and are left as zero.
+fn outline_glyph<'f>(
+    font: FontRef<'f>,
+    gid: u16,
+    scale: Scale<Font, Font>,
+) -> Result<Glyph<'f>, FontError> {
+    use font::Font;
+    let hmetrics = font
+        .glyph_metrics(gid)
+        .ok_or(FontError::MissingGlyphGID(gid))?;
+    let glyph = font
+        .glyph(GlyphId(gid as u32))
+        .ok_or(FontError::MissingGlyphGID(gid))?;
+    let bbox = glyph.path.bounds();
+    let ll = bbox.lower_left();
+    let ur = bbox.upper_right();
+    let length = |value: f32| Length::new(value, Font) * scale;
+
+    Ok(Glyph {
+        gid,
+        font,
+        advance: length(hmetrics.advance),
+        lsb: length(hmetrics.lsb),
+        italics: Length::zero(),
+        attachment: Length::zero(),
+        bbox: (
+            length(ll.x()),
+            length(ur.y()),
+            length(ur.x()),
+            length(ll.y()),
+        ),
+    })
+}
+
+#[derive(Clone)]
 pub struct Glyph<'f> {
-    pub font: &'f MathFont,
+    pub font: FontRef<'f>,
     pub gid: u16,
     // x_min, y_min, x_max, y_max
     pub bbox: (Length<Font>, Length<Font>, Length<Font>, Length<Font>),
@@ -271,6 +648,9 @@ impl<'f> Glyph<'f> {
 pub struct Style {
     pub family: Family,
     pub weight: Weight,
+    pub convention: MathStyleConvention,
+    /// Symbols are made bold after their shape is chosen, as done by `\boldsymbol`.
+    pub bold_symbol: bool,
 }
 
 impl Style {
@@ -305,6 +685,29 @@ impl Style {
             ..self
         }
     }
+
+    pub fn with_convention(self, convention: MathStyleConvention) -> Style {
+        Style { convention, ..self }
+    }
+
+    /// Upright letters, keeping the current weight.  This is used by `\mathup`.
+    pub fn with_upright(self) -> Style {
+        Style {
+            family: Family::Roman,
+            weight: self.weight.without_italics(),
+            ..self
+        }
+    }
+
+    /// Similar to `with_bold`, except that symbols keep the italic or upright
+    /// shape they would have otherwise, including the shape chosen by the
+    /// convention.  This is what is used by `\boldsymbol`.
+    pub fn with_bold_symbol(self) -> Style {
+        Style {
+            bold_symbol: true,
+            ..self
+        }
+    }
 }
 
 // NB: Changing the order of these variants requires
@@ -344,6 +747,33 @@ impl Weight {
             _ => Weight::Italic,
         }
     }
+
+    fn without_italics(self) -> Self {
+        match self {
+            Weight::Bold | Weight::BoldItalic => Weight::Bold,
+            _ => Weight::None,
+        }
+    }
+}
+
+/// The convention which decides which letters are italic by default,
+/// similar to the `math-style` option of unicode-math.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum MathStyleConvention {
+    /// Italic Latin and lowercase Greek, with upright capital Greek.
+    TeX,
+    /// Italic Latin and Greek, as mandated by ISO 80000-2.
+    ISO,
+    /// Italic lowercase Latin, everything else is upright.
+    French,
+    /// Upright Latin and Greek.
+    Upright,
+}
+
+impl Default for MathStyleConvention {
+    fn default() -> MathStyleConvention {
+        MathStyleConvention::TeX
+    }
 }
 
 impl Default for Family {
@@ -1,4 +1,4 @@
-use super::Style;
+use super::{Family, MathStyleConvention, Style, Weight};
 
 // BMP codepoint points of each symbol group.
 const UPPER_A: u32 = 0x41;
@@ -75,10 +75,15 @@ static DIGIT_LUT: [u32; 28] = [
 /// Take a codepoint and a stlye (a weight and family pair), and apply the
 /// current font style to the given codepoint.
 pub fn style_symbol(codepoint: char, style: Style) -> char {
+    if style.bold_symbol {
+        return bold_symbol(codepoint, style);
+    }
+
     let codepoint = codepoint as u32;
+    let style = apply_convention(codepoint, style);
     let cp = match codepoint {
-        LOWER_A..=LOWER_Z => style_lookup(&LATIN_LOWER_LUT, codepoint, style),
-        UPPER_A..=UPPER_Z => style_lookup(&LATIN_UPPER_LUT, codepoint, style),
+        LOWER_A..=LOWER_Z => letterlike(style_lookup(&LATIN_LOWER_LUT, codepoint, style)),
+        UPPER_A..=UPPER_Z => letterlike(style_lookup(&LATIN_UPPER_LUT, codepoint, style)),
         UPPER_ALPHA..=UPPER_OMEGA => style_lookup(&GREEK_UPPER_LUT, codepoint, style),
         LOWER_ALPHA..=LOWER_OMEGA => style_lookup(&GREEK_LOWER_LUT, codepoint, style),
         DIGIT_0..=DIGIT_9 => style_lookup(&DIGIT_LUT, codepoint, style),
@@ -87,44 +92,271 @@ pub fn style_symbol(codepoint: char, style: Style) -> char {
     std::char::from_u32(cp).unwrap()
 }
 
+/// Bold symbols keep the shape of the symbol without bold, so that `\Gamma`
+/// stays upright and `x` stays italic in the TeX convention.
+fn bold_symbol(codepoint: char, style: Style) -> char {
+    let style = Style {
+        bold_symbol: false,
+        ..style
+    };
+    let bold = if is_math_italic(style_symbol(codepoint, style)) {
+        style.with_weight(Weight::BoldItalic)
+    } else if style.family == Family::Normal {
+        style.with_family(Family::Roman).with_weight(Weight::Bold)
+    } else {
+        style.with_weight(Weight::Bold)
+    };
+    style_symbol(codepoint, bold)
+}
+
+/// Without an explicit family or weight, the convention decides whether
+/// a letter is italic (`Family::Normal`) or upright (`Family::Roman`).
+fn apply_convention(codepoint: u32, style: Style) -> Style {
+    if style.family != Family::Normal || style.weight != Weight::None {
+        return style;
+    }
+
+    let upright = match style.convention {
+        MathStyleConvention::TeX => matches!(codepoint, UPPER_ALPHA..=UPPER_OMEGA | 0x3F4),
+        MathStyleConvention::ISO => false,
+        MathStyleConvention::French => !matches!(codepoint, LOWER_A..=LOWER_Z | 0x131 | 0x237),
+        MathStyleConvention::Upright => true,
+    };
+
+    if upright {
+        style.with_family(Family::Roman)
+    } else {
+        style
+    }
+}
+
 fn style_lookup(lut: &[u32], codepoint: u32, style: Style) -> u32 {
     let y = style.family as usize;
     let x = style.weight as usize;
     codepoint + lut[4 * y + x]
 }
 
-// TODO: Investigate these symbols.
+// The Mathematical Alphanumeric Symbols block has holes for the letters
+// which were already encoded in the Letterlike Symbols block.
+#[cfg_attr(rustfmt, rustfmt_skip)]
+static LETTERLIKE_HOLES: [(u32, u32); 24] = [
+    (0x1D455, 0x210E),   // Italic h
+    (0x1D49D, 0x212C),   // Script B
+    (0x1D4A0, 0x2130),   // Script E
+    (0x1D4A1, 0x2131),   // Script F
+    (0x1D4A3, 0x210B),   // Script H
+    (0x1D4A4, 0x2110),   // Script I
+    (0x1D4A7, 0x2112),   // Script L
+    (0x1D4A8, 0x2133),   // Script M
+    (0x1D4AD, 0x211B),   // Script R
+    (0x1D4BA, 0x212F),   // Script e
+    (0x1D4BC, 0x210A),   // Script g
+    (0x1D4C4, 0x2134),   // Script o
+    (0x1D506, 0x212D),   // Fraktur C
+    (0x1D50B, 0x210C),   // Fraktur H
+    (0x1D50C, 0x2111),   // Fraktur I
+    (0x1D515, 0x211C),   // Fraktur R
+    (0x1D51D, 0x2128),   // Fraktur Z
+    (0x1D53A, 0x2102),   // Double-struck C
+    (0x1D53F, 0x210D),   // Double-struck H
+    (0x1D545, 0x2115),   // Double-struck N
+    (0x1D547, 0x2119),   // Double-struck P
+    (0x1D548, 0x211A),   // Double-struck Q
+    (0x1D549, 0x211D),   // Double-struck R
+    (0x1D551, 0x2124),   // Double-struck Z
+];
+
+fn letterlike(codepoint: u32) -> u32 {
+    LETTERLIKE_HOLES
+        .iter()
+        .find(|&&(hole, _)| hole == codepoint)
+        .map(|&(_, letter)| letter)
+        .unwrap_or(codepoint)
+}
+
 // Exceptional glyphs that are not found in BMP
 // and don't belong in the previous groups
 // The handling of these values can be reference
 //   codepoint-math-usv.dtx in codepoint-math
 //   RenderMathMLToken.cpp in WebKit
 //
-// const VAR_UPPER_THETA:      u32 = 0x3F4;
-// const VAR_DIGAMMA:          u32 = 0x3DC;  ??
-// const VAR_EPSILON:          u32 = 0x3F5;
-// const VAR_THETA:            u32 = 0x3D1;
-// const VAR_KAPPA:            u32 = 0x3F0;
-// const VAR_PHI:              u32 = 0x3D5;
-// const VAR_RHO:              u32 = 0x3F1;
-// const VAR_PI:               u32 = 0x3D6;
-// const DIGAMMA:              u32 = 0x3DD; ??
-// const PARTIAL_DIFFERENTIAL: u32 = 0x2202;
-// const NABLA:                u32 = 0x2207;
-
-// const BOLD_VAR_UPPER_THETA:      u32 = 0x1D6B9;
-// const BOLD_VAR_EPSILON:          u32 = 0x1D6DC;
-// const BOLD_VAR_THETA:            u32 = 0x1D6DD;
-// const BOLD_VAR_KAPPA:            u32 = 0x1D6DE;
-// const BOLD_VAR_PHI:              u32 = 0x1D6DF;
-// const BOLD_VAR_RHO:              u32 = 0x1D6E0;
-// const BOLD_VAR_PI:               u32 = 0x1D6E1;
-// const BOLD_NABLA:                u32 = 0x1D6C1;
-// const BOLD_PARTIAL_DIFFERENTIAL: u32 = 0x1D6DB;
-
-fn style_other(sym: u32, _: Style) -> u32 {
-    match sym {
-        0x2D => 0x2212, // Minus sign
-        _ => sym,
+// The `Normal` column is used for the default weight of `Family::Normal`,
+// other families without a weight leave the codepoint unchanged.  The sans
+// serif family only has bold Greek, and otherwise uses the serif forms.
+#[cfg_attr(rustfmt, rustfmt_skip)]
+static EXCEPTIONS: [(u32, [u32; 6]); 13] = [
+//  Codepoint, Normal,  Italic,  Bold,    BoldItalic, SansBold, SansBoldItalic
+    (0x3F4,  [0x1D6F3, 0x1D6F3, 0x1D6B9, 0x1D72D, 0x1D767, 0x1D7A1]),   // VAR_UPPER_THETA
+    (0x2207, [0x2207,  0x1D6FB, 0x1D6C1, 0x1D735, 0x1D76F, 0x1D7A9]),   // NABLA
+    (0x2202, [0x1D715, 0x1D715, 0x1D6DB, 0x1D74F, 0x1D789, 0x1D7C3]),   // PARTIAL_DIFFERENTIAL
+    (0x3F5,  [0x1D716, 0x1D716, 0x1D6DC, 0x1D750, 0x1D78A, 0x1D7C4]),   // VAR_EPSILON
+    (0x3D1,  [0x1D717, 0x1D717, 0x1D6DD, 0x1D751, 0x1D78B, 0x1D7C5]),   // VAR_THETA
+    (0x3F0,  [0x1D718, 0x1D718, 0x1D6DE, 0x1D752, 0x1D78C, 0x1D7C6]),   // VAR_KAPPA
+    (0x3D5,  [0x1D719, 0x1D719, 0x1D6DF, 0x1D753, 0x1D78D, 0x1D7C7]),   // VAR_PHI
+    (0x3F1,  [0x1D71A, 0x1D71A, 0x1D6E0, 0x1D754, 0x1D78E, 0x1D7C8]),   // VAR_RHO
+    (0x3D6,  [0x1D71B, 0x1D71B, 0x1D6E1, 0x1D755, 0x1D78F, 0x1D7C9]),   // VAR_PI
+    (0x3DC,  [0x3DC,   0x3DC,   0x1D7CA, 0x1D7CA, 0x1D7CA, 0x1D7CA]),   // DIGAMMA
+    (0x3DD,  [0x3DD,   0x3DD,   0x1D7CB, 0x1D7CB, 0x1D7CB, 0x1D7CB]),   // SMALL_DIGAMMA
+    (0x131,  [0x1D6A4, 0x1D6A4, 0x131,   0x131,   0x131,   0x131  ]),   // DOTLESS_I
+    (0x237,  [0x1D6A5, 0x1D6A5, 0x237,   0x237,   0x237,   0x237  ]),   // DOTLESS_J
+];
+
+fn style_other(sym: u32, style: Style) -> u32 {
+    if sym == 0x2D {
+        return 0x2212; // Minus sign
+    }
+
+    let sans = style.family == Family::SansSerif;
+    let column = match (style.weight, sans) {
+        (Weight::None, _) if style.family == Family::Normal => 0,
+        (Weight::None, _) => return sym,
+        (Weight::Italic, _) => 1,
+        (Weight::Bold, false) => 2,
+        (Weight::BoldItalic, false) => 3,
+        (Weight::Bold, true) => 4,
+        (Weight::BoldItalic, true) => 5,
+    };
+
+    EXCEPTIONS
+        .iter()
+        .find(|&&(cp, _)| cp == sym)
+        .map(|&(_, styled)| styled[column])
+        .unwrap_or(sym)
+}
+
+/// Returns true if the codepoint is found in the Mathematical Alphanumeric
+/// Symbols block, as is the case for symbols styled by `style_symbol`.
+pub fn is_math_alphanumeric(codepoint: char) -> bool {
+    ('\u{1D400}'..='\u{1D7FF}').contains(&codepoint)
+}
+
+/// The dotless form of `i` and `j`, in the alphabets where Unicode has one.
+pub fn dotless_codepoint(codepoint: char) -> Option<char> {
+    match codepoint {
+        'i' => Some('\u{131}'),
+        'j' => Some('\u{237}'),
+        '\u{1D456}' => Some('\u{1D6A4}'),
+        '\u{1D457}' => Some('\u{1D6A5}'),
+        _ => None,
+    }
+}
+
+/// Returns true if the codepoint is an `i` or `j` from any math alphabet.
+/// These letters lose their dot when placed under an accent.
+pub fn is_dotted(codepoint: char) -> bool {
+    match codepoint {
+        'i' | 'j' => true,
+        // Each alphabet has 26 capital letters followed by 26 small letters.
+        '\u{1D400}'..='\u{1D6A3}' => {
+            let offset = (codepoint as u32 - 0x1D400) % 52;
+            offset == 26 + 8 || offset == 26 + 9
+        }
+        _ => false,
+    }
+}
+
+/// Returns true if the codepoint is a slanted letter, such as those
+/// produced by `style_symbol` for `\mathit` and `\boldsymbol`.
+pub fn is_math_italic(codepoint: char) -> bool {
+    match codepoint {
+        '\u{210E}' // Planck constant, the italic h
+        | '\u{1D434}'..='\u{1D49B}' // Italic and bold italic Latin
+        | '\u{1D608}'..='\u{1D66F}' // Sans-serif italic and bold italic Latin
+        | '\u{1D6A4}'..='\u{1D6A5}' // Dotless italic i and j
+        | '\u{1D6E2}'..='\u{1D755}' // Italic and bold italic Greek
+        | '\u{1D790}'..='\u{1D7C9}' => true, // Sans-serif bold italic Greek
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::style_symbol;
+    use crate::font::{Family, MathStyleConvention, Style, Weight};
+
+    #[test]
+    fn letterlike_holes() {
+        let style = |family| Style::new().with_family(family);
+        assert_eq!(style_symbol('h', Style::new()), 'ℎ');
+        assert_eq!(style_symbol('R', style(Family::Blackboard)), 'ℝ');
+        assert_eq!(style_symbol('S', style(Family::Blackboard)), '𝕊');
+        assert_eq!(style_symbol('H', style(Family::Script)), 'ℋ');
+        assert_eq!(style_symbol('e', style(Family::Script)), 'ℯ');
+        assert_eq!(style_symbol('Z', style(Family::Fraktur)), 'ℨ');
+        assert_eq!(style_symbol('H', style(Family::Script).with_bold()), '𝓗');
+    }
+
+    #[test]
+    fn greek_variants() {
+        let roman = Style::new().with_family(Family::Roman);
+        let sans = Style::new().with_family(Family::SansSerif);
+        assert_eq!(style_symbol('ϑ', Style::new()), '𝜗');
+        assert_eq!(style_symbol('ϑ', roman), 'ϑ');
+        assert_eq!(style_symbol('∇', Style::new()), '∇');
+        assert_eq!(style_symbol('∇', Style::new().with_bold()), '𝛁');
+        assert_eq!(
+            style_symbol('∂', Style::new().with_weight(Weight::BoldItalic)),
+            '𝝏'
+        );
+        assert_eq!(style_symbol('ϕ', sans.with_bold()), '𝞍');
+        assert_eq!(style_symbol('ı', Style::new()), '𝚤');
+        assert_eq!(style_symbol('ı', roman), 'ı');
+    }
+
+    #[test]
+    fn conventions() {
+        let style = |convention| Style::new().with_convention(convention);
+        let cases = [
+            (MathStyleConvention::TeX, ['𝑥', '𝑋', '𝛼', 'Γ']),
+            (MathStyleConvention::ISO, ['𝑥', '𝑋', '𝛼', '𝛤']),
+            (MathStyleConvention::French, ['𝑥', 'X', 'α', 'Γ']),
+            (MathStyleConvention::Upright, ['x', 'X', 'α', 'Γ']),
+        ];
+
+        for &(convention, expected) in cases.iter() {
+            let styled = ['x', 'X', 'α', 'Γ'].map(|c| style_symbol(c, style(convention)));
+            assert_eq!(styled, expected, "{:?}", convention);
+        }
+
+        // Explicit styles are unaffected by the convention.
+        let upright = style(MathStyleConvention::ISO).with_upright();
+        assert_eq!(style_symbol('α', upright), 'α');
+        let italic = style(MathStyleConvention::Upright).with_italics();
+        assert_eq!(style_symbol('x', italic), '𝑥');
+    }
+
+    #[test]
+    fn bold_symbols() {
+        let bold = |convention| Style::new().with_convention(convention).with_bold_symbol();
+        let cases = [
+            (
+                MathStyleConvention::TeX,
+                ['𝒙', '𝑿', '𝜶', '𝚪', '𝟐', '𝛁', '𝝏'],
+            ),
+            (
+                MathStyleConvention::ISO,
+                ['𝒙', '𝑿', '𝜶', '𝜞', '𝟐', '𝛁', '𝝏'],
+            ),
+            (
+                MathStyleConvention::French,
+                ['𝒙', '𝐗', '𝛂', '𝚪', '𝟐', '𝛁', '𝛛'],
+            ),
+            (
+                MathStyleConvention::Upright,
+                ['𝐱', '𝐗', '𝛂', '𝚪', '𝟐', '𝛁', '𝛛'],
+            ),
+        ];
+
+        for &(convention, expected) in cases.iter() {
+            let styled =
+                ['x', 'X', 'α', 'Γ', '2', '∇', '∂'].map(|c| style_symbol(c, bold(convention)));
+            assert_eq!(styled, expected, "{:?}", convention);
+        }
+
+        // Explicit families and weights are emboldened as they are.
+        let bold = bold(MathStyleConvention::TeX);
+        assert_eq!(style_symbol('x', bold.with_family(Family::Roman)), '𝐱');
+        assert_eq!(style_symbol('Γ', bold.with_italics()), '𝜞');
+        assert_eq!(style_symbol('x', bold.with_family(Family::SansSerif)), '𝘅');
     }
 }
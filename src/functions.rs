@@ -1,12 +1,14 @@
 use crate::dimensions::Unit;
 use crate::error::{ParseError, ParseResult};
-use crate::font::{style_symbol, AtomType, Family, Style, Weight};
+use crate::font::{AtomType, Style};
 use crate::layout::Style as LayoutStyle;
 use crate::lexer::{Lexer, Token};
 use crate::parser as parse;
 use crate::parser::color::RGBA;
 use crate::parser::nodes::{
-    AtomChange, BarThickness, Color, GenFraction, MathStyle, ParseNode, Radical, Rule, Stack,
+    AtomChange, BarThickness, Cancel, CancelKind, Color, FrameColor, Framed, GenFraction, Lap,
+    LapKind, MathChoice, MathStyle, MathVersion, ParseNode, Phantom, PhantomKind, Radical, Raise,
+    Rule, Scripts, Stack,
 };
 use crate::parser::symbols::Symbol;
 
@@ -39,6 +41,24 @@ pub enum Command {
     AtomChange(AtomType),
     TextOperator(&'static str, bool),
     SubStack(AtomType),
+    Not,
+    Cancel(CancelKind),
+    Boxed,
+    ColorBox(bool),
+    Phantom(PhantomKind),
+    Smash,
+    MathStrut,
+    Lap(LapKind),
+    RaiseBox,
+    Raise(bool),
+    MathChoice,
+    PoorBold,
+    Prescript,
+    Tensor,
+    ItalicCorrection,
+    Text,
+    OperatorName,
+    MathVersion(Option<MathVersion>),
 }
 
 #[cfg_attr(rustfmt, rustfmt_skip)]
@@ -58,12 +78,88 @@ impl Command {
             AtomChange(a)        => atom_change(lex, local, a),
             TextOperator(a, b)   => text_operator(lex, local, a, b),
             SubStack(a)          => substack(lex, local, a),
+            Not                  => not(lex, local),
+            Cancel(a)            => cancel(lex, local, a),
+            Boxed                => boxed(lex, local),
+            ColorBox(a)          => color_box(lex, local, a),
+            Phantom(a)           => phantom(lex, local, a),
+            Smash                => smash(lex, local),
+            MathStrut            => math_strut(lex, local),
+            Lap(a)               => lap(lex, local, a),
+            RaiseBox             => raise_box(lex, local),
+            Raise(a)             => raise(lex, local, a),
+            MathChoice           => math_choice(lex, local),
+            PoorBold             => poor_bold(lex, local),
+            Prescript            => prescript(lex, local),
+            Tensor               => tensor(lex, local),
+            ItalicCorrection     => Ok(ParseNode::ItalicCorrection),
+            Text                 => text(lex, local),
+            OperatorName         => operator_name(lex, local),
+            MathVersion(a)       => math_version(lex, local, a),
         }
     }
 }
 
-pub fn get_command(name: &str) -> Option<Command> {
-    let command = match name {
+impl Command {
+    /// The codepoints laid out from the math font by this command, apart from
+    /// those given in its arguments.
+    pub fn codepoints(&self) -> Vec<char> {
+        match *self {
+            Command::Radical => vec!['\u{221A}'],
+            Command::Fraction(left, right, _, _) => left
+                .into_iter()
+                .chain(right)
+                .map(|sym| sym.codepoint)
+                .collect(),
+            Command::MathStrut => vec!['('],
+            // A solidus is overlaid on symbols without a negated form.
+            Command::Not => vec!['/'],
+            _ => Vec::new(),
+        }
+    }
+
+    /// The codepoints set by this command as running text, which uses the text
+    /// font when one is given.
+    pub fn text_codepoints(&self) -> Vec<char> {
+        match *self {
+            Command::TextOperator(text, _) => text.chars().filter(|&c| c != ',').collect(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// Commands handled by the parser itself rather than through `get_command`,
+/// such as font changes and `\left ... \right`.  They lay out nothing from the
+/// font apart from the symbols given in their arguments.
+pub const PARSER_COMMAND_NAMES: &[&str] = &[
+    // Font changes
+    "mathbf",
+    "mathit",
+    "mathrm",
+    "mathup",
+    "mathscr",
+    "mathfrak",
+    "mathbb",
+    "mathsf",
+    "mathtt",
+    "mathcal",
+    "boldsymbol",
+    "bm",
+    // Colors
+    "color",
+    "definecolor",
+    // Implicit groups and environments
+    "left",
+    "right",
+    "begin",
+    "end",
+    // Limits of operators
+    "limits",
+    "nolimits",
+];
+
+name_table! {
+    pub fn get_command(name) -> Command, COMMAND_NAMES {
         "frac" => Command::Fraction(None, None, BarThickness::Default, MathStyle::NoChange),
         "tfrac" => Command::Fraction(None, None, BarThickness::Default, MathStyle::Text),
         "dfrac" => Command::Fraction(None, None, BarThickness::Default, MathStyle::Display),
@@ -128,18 +224,71 @@ pub fn get_command(name: &str) -> Option<Command> {
         "displaystyle" => Command::Style(LayoutStyle::Display),
         "scriptstyle" => Command::Style(LayoutStyle::Script),
         "scriptscriptstyle" => Command::Style(LayoutStyle::ScriptScript),
+        "mathchoice" => Command::MathChoice,
+
+        // Poor man's bold, see also `\boldsymbol` which is a state change.
+        "pmb" => Command::PoorBold,
 
         // Atom-type changes
         "mathop" => Command::AtomChange(AtomType::Operator(false)),
         "mathrel" => Command::AtomChange(AtomType::Relation),
         "mathord" => Command::AtomChange(AtomType::Alpha),
 
-        // Color related
-        "color" => Command::Color,
+        // Color related, note that `\color` is a switch handled by the parser.
+        "textcolor" => Command::Color,
         "blue" => Command::ColorLit(RGBA(0, 0, 0xff, 0xff)),
         "red" => Command::ColorLit(RGBA(0xff, 0, 0, 0xff)),
         "gray" => Command::ColorLit(RGBA(0x80, 0x80, 0x80, 0xff)),
-        "phantom" => Command::ColorLit(RGBA(0, 0, 0, 0)),
+        "colorbox" => Command::ColorBox(false),
+        "fcolorbox" => Command::ColorBox(true),
+
+        // Phantoms and struts
+        "phantom" => Command::Phantom(PhantomKind::Full),
+        "hphantom" => Command::Phantom(PhantomKind::Horizontal),
+        "vphantom" => Command::Phantom(PhantomKind::Vertical),
+        "smash" => Command::Smash,
+        "mathstrut" => Command::MathStrut,
+        "strut" => Command::MathStrut,
+
+        // Overlaps and shifts
+        "llap" => Command::Lap(LapKind::Left),
+        "rlap" => Command::Lap(LapKind::Right),
+        "clap" => Command::Lap(LapKind::Center),
+        "mathllap" => Command::Lap(LapKind::Left),
+        "mathrlap" => Command::Lap(LapKind::Right),
+        "mathclap" => Command::Lap(LapKind::Center),
+        "raisebox" => Command::RaiseBox,
+        "raise" => Command::Raise(false),
+        "lower" => Command::Raise(true),
+
+        // Italic correction
+        "/" => Command::ItalicCorrection,
+
+        // Math versions
+        "mathversion" => Command::MathVersion(None),
+        "boldmath" => Command::MathVersion(Some(MathVersion::Bold)),
+        "unboldmath" => Command::MathVersion(Some(MathVersion::Normal)),
+
+        // Text and operator names, set in the text font
+        "text" => Command::Text,
+        "textrm" => Command::Text,
+        "mbox" => Command::Text,
+        "operatorname" => Command::OperatorName,
+
+        // Pre-scripts and tensor indices
+        "prescript" => Command::Prescript,
+        "tensor" => Command::Tensor,
+
+        // Framed boxes
+        "boxed" => Command::Boxed,
+        "fbox" => Command::Boxed,
+
+        // Negation and cancellation
+        "not" => Command::Not,
+        "cancel" => Command::Cancel(CancelKind::Forward),
+        "bcancel" => Command::Cancel(CancelKind::Backward),
+        "xcancel" => Command::Cancel(CancelKind::Cross),
+        "cancelto" => Command::Cancel(CancelKind::To),
 
         // Operators with limits
         "det" => Command::TextOperator("det", true),
@@ -177,9 +326,7 @@ pub fn get_command(name: &str) -> Option<Command> {
         "Ker" => Command::TextOperator("Ker", false),
         "ln" => Command::TextOperator("ln", false),
         "log" => Command::TextOperator("log", false),
-        _ => return None,
-    };
-    Some(command)
+    }
 }
 
 fn radical<'a>(lex: &mut Lexer<'a>, local: Style) -> ParseResult<'a, ParseNode> {
@@ -213,7 +360,7 @@ fn v_extend<'a>(lex: &mut Lexer<'a>, local: Style) -> ParseResult<'a, ParseNode>
 }
 
 fn color<'a>(lex: &mut Lexer<'a>, local: Style) -> ParseResult<'a, ParseNode> {
-    let color = parse::required_argument_with(lex, local, parse::color)?;
+    let color = parse::color_argument(lex, local)?;
     let inner = parse::required_argument(lex, local)?;
     Ok(ParseNode::Color(Color { color, inner }))
 }
@@ -223,6 +370,58 @@ fn color_lit<'a>(lex: &mut Lexer<'a>, local: Style, color: RGBA) -> ParseResult<
     Ok(ParseNode::Color(Color { color, inner }))
 }
 
+fn not<'a>(lex: &mut Lexer<'a>, local: Style) -> ParseResult<'a, ParseNode> {
+    let inner = parse::required_argument(lex, local)?;
+
+    // Prefer the precomposed glyph, ie: `\not=` is `≠`.
+    if let [ParseNode::Symbol(sym)] = inner.as_slice() {
+        if let Some(negated) = sym.negated() {
+            return Ok(ParseNode::Symbol(negated));
+        }
+    }
+
+    Ok(ParseNode::Cancel(Cancel {
+        kind: CancelKind::Negation,
+        inner,
+        label: None,
+    }))
+}
+
+fn cancel<'a>(lex: &mut Lexer<'a>, local: Style, kind: CancelKind) -> ParseResult<'a, ParseNode> {
+    let label = match kind {
+        CancelKind::To => Some(parse::required_argument(lex, local)?),
+        _ => None,
+    };
+
+    let inner = parse::required_argument(lex, local)?;
+    Ok(ParseNode::Cancel(Cancel { kind, inner, label }))
+}
+
+fn boxed<'a>(lex: &mut Lexer<'a>, local: Style) -> ParseResult<'a, ParseNode> {
+    let inner = parse::required_argument(lex, local)?;
+    Ok(ParseNode::Framed(Framed {
+        inner,
+        frame: FrameColor::Inherit,
+        background: None,
+    }))
+}
+
+/// Parse `\colorbox{bg}{..}`, or `\fcolorbox{frame}{bg}{..}` if `framed` is set.
+fn color_box<'a>(lex: &mut Lexer<'a>, local: Style, framed: bool) -> ParseResult<'a, ParseNode> {
+    let frame = match framed {
+        true => FrameColor::Color(parse::color_argument(lex, local)?),
+        false => FrameColor::None,
+    };
+    let background = parse::color_argument(lex, local)?;
+    let inner = parse::required_argument(lex, local)?;
+
+    Ok(ParseNode::Framed(Framed {
+        inner,
+        frame,
+        background: Some(background),
+    }))
+}
+
 fn fraction<'a>(
     lex: &mut Lexer<'a>,
     local: Style,
@@ -262,6 +461,24 @@ fn style<'a>(_: &mut Lexer<'a>, _: Style, new_style: LayoutStyle) -> ParseResult
     Ok(ParseNode::Style(new_style))
 }
 
+/// `\boldmath` and `\unboldmath` name their version, while `\mathversion`
+/// takes it as an argument.
+fn math_version<'a>(
+    lex: &mut Lexer<'a>,
+    _: Style,
+    version: Option<MathVersion>,
+) -> ParseResult<'a, ParseNode> {
+    let version = match version {
+        Some(version) => version,
+        None => match lex.group()? {
+            "normal" => MathVersion::Normal,
+            "bold" => MathVersion::Bold,
+            other => return Err(ParseError::UnrecognizedMathVersion(other)),
+        },
+    };
+    Ok(ParseNode::MathVersion(version))
+}
+
 fn atom_change<'a>(lex: &mut Lexer<'a>, local: Style, at: AtomType) -> ParseResult<'a, ParseNode> {
     let inner = parse::required_argument(lex, local)?;
     Ok(ParseNode::AtomChange(AtomChange { at, inner }))
@@ -275,27 +492,38 @@ fn text_operator<'a>(
 ) -> ParseResult<'a, ParseNode> {
     const SMALL_SKIP: Unit = Unit::Em(3f64 / 18f64);
     let at = AtomType::Operator(limits);
-    let mut inner = Vec::with_capacity(text.len());
+    let mut inner = Vec::new();
 
-    for c in text.chars() {
-        if c == ',' {
+    for (idx, word) in text.split(',').enumerate() {
+        if idx > 0 {
             inner.push(ParseNode::Kerning(SMALL_SKIP));
-        } else {
-            inner.push(ParseNode::Symbol(Symbol {
-                codepoint: style_symbol(
-                    c,
-                    Style::default()
-                        .with_family(Family::Roman)
-                        .with_weight(Weight::None),
-                ),
-                atom_type: AtomType::Ordinal,
-            }));
         }
+        inner.push(ParseNode::Text(word.to_string()));
     }
 
     Ok(ParseNode::AtomChange(AtomChange { at, inner }))
 }
 
+fn text<'a>(lex: &mut Lexer<'a>, _: Style) -> ParseResult<'a, ParseNode> {
+    let text = lex.group()?;
+    Ok(ParseNode::Text(text.to_string()))
+}
+
+/// `\operatorname{name}` behaves like `\sin`, while `\operatorname*{name}`
+/// places its scripts as limits like `\lim`.
+fn operator_name<'a>(lex: &mut Lexer<'a>, _: Style) -> ParseResult<'a, ParseNode> {
+    let limits = lex.current == Token::Symbol('*');
+    if limits {
+        lex.next();
+    }
+
+    let name = lex.group()?;
+    Ok(ParseNode::AtomChange(AtomChange {
+        at: AtomType::Operator(limits),
+        inner: vec![ParseNode::Text(name.to_string())],
+    }))
+}
+
 fn substack<'a>(
     lex: &mut Lexer<'a>,
     local: Style,
@@ -326,3 +554,208 @@ fn substack<'a>(
     lex.next();
     Ok(ParseNode::Stack(Stack { atom_type, lines }))
 }
+
+fn phantom<'a>(lex: &mut Lexer<'a>, local: Style, kind: PhantomKind) -> ParseResult<'a, ParseNode> {
+    let inner = parse::required_argument(lex, local)?;
+    Ok(ParseNode::Phantom(Phantom { kind, inner }))
+}
+
+fn smash<'a>(lex: &mut Lexer<'a>, local: Style) -> ParseResult<'a, ParseNode> {
+    let position =
+        parse::optional_argument_with(lex, local, |lex, _| Ok(Some(lex.alphanumeric())))?;
+    let kind = match position {
+        None => PhantomKind::Smash {
+            top: true,
+            bottom: true,
+        },
+        Some("t") => PhantomKind::Smash {
+            top: true,
+            bottom: false,
+        },
+        Some("b") => PhantomKind::Smash {
+            top: false,
+            bottom: true,
+        },
+        Some(pos) => return Err(ParseError::UnrecognizedPosition(pos)),
+    };
+
+    let inner = parse::required_argument(lex, local)?;
+    Ok(ParseNode::Phantom(Phantom { kind, inner }))
+}
+
+/// `\mathstrut` has the height and depth of a parenthesis and no width.
+/// Without a notion of `\baselineskip`, `\strut` is treated the same.
+fn math_strut<'a>(_: &mut Lexer<'a>, _: Style) -> ParseResult<'a, ParseNode> {
+    Ok(ParseNode::Phantom(Phantom {
+        kind: PhantomKind::Vertical,
+        inner: vec![ParseNode::Symbol(Symbol {
+            codepoint: '(',
+            atom_type: AtomType::Open,
+        })],
+    }))
+}
+
+fn lap<'a>(lex: &mut Lexer<'a>, local: Style, kind: LapKind) -> ParseResult<'a, ParseNode> {
+    let inner = parse::required_argument(lex, local)?;
+    Ok(ParseNode::Lap(Lap { kind, inner }))
+}
+
+/// Parse `\raisebox{shift}[height][depth]{inner}`.
+fn raise_box<'a>(lex: &mut Lexer<'a>, local: Style) -> ParseResult<'a, ParseNode> {
+    let shift = parse::required_argument_with(lex, local, parse::dimension)?;
+    let height = parse::optional_argument_with(lex, local, |lex, _| lex.dimension())?;
+    let depth = parse::optional_argument_with(lex, local, |lex, _| lex.dimension())?;
+    let inner = parse::required_argument(lex, local)?;
+    Ok(ParseNode::Raise(Raise {
+        shift,
+        height,
+        depth,
+        inner,
+    }))
+}
+
+/// Parse `\raise<dimension>{inner}` and `\lower<dimension>{inner}`.
+fn raise<'a>(lex: &mut Lexer<'a>, local: Style, lower: bool) -> ParseResult<'a, ParseNode> {
+    lex.consume_whitespace();
+    let shift = parse::dimension(lex, local)?;
+    let shift = if lower { -shift } else { shift };
+    let inner = parse::required_argument(lex, local)?;
+    Ok(ParseNode::Raise(Raise {
+        shift,
+        height: None,
+        depth: None,
+        inner,
+    }))
+}
+
+fn math_choice<'a>(lex: &mut Lexer<'a>, local: Style) -> ParseResult<'a, ParseNode> {
+    let display = parse::required_argument(lex, local)?;
+    let text = parse::required_argument(lex, local)?;
+    let script = parse::required_argument(lex, local)?;
+    let script_script = parse::required_argument(lex, local)?;
+    Ok(ParseNode::MathChoice(MathChoice {
+        display,
+        text,
+        script,
+        script_script,
+    }))
+}
+
+fn poor_bold<'a>(lex: &mut Lexer<'a>, local: Style) -> ParseResult<'a, ParseNode> {
+    let inner = parse::required_argument(lex, local)?;
+    Ok(ParseNode::PoorBold(inner))
+}
+
+/// `\prescript{sup}{sub}{base}` from mathtools.  An empty script is omitted.
+fn prescript<'a>(lex: &mut Lexer<'a>, local: Style) -> ParseResult<'a, ParseNode> {
+    let sup = parse::required_argument(lex, local)?;
+    let sub = parse::required_argument(lex, local)?;
+    let base = parse::required_argument(lex, local)?;
+
+    let non_empty = |script: Vec<ParseNode>| Some(script).filter(|s| !s.is_empty());
+    Ok(ParseNode::Scripts(Scripts::with_prescripts(
+        single_or_group(base),
+        non_empty(sup),
+        non_empty(sub),
+    )))
+}
+
+/// `\tensor[^a_b]{X}{^c_d^e}` from the tensor package.  Each index is
+/// staggered, so that it is placed to the right of the previous index.
+/// The optional argument holds the pre-scripts, which are not staggered.
+fn tensor<'a>(lex: &mut Lexer<'a>, local: Style) -> ParseResult<'a, ParseNode> {
+    let pre = parse::optional_argument_with(lex, local, |lex, local| {
+        tensor_indices(lex, local).map(Some)
+    })?;
+    let base = single_or_group(parse::required_argument(lex, local)?);
+
+    lex.consume_whitespace();
+    lex.current.expect_symbol('{')?;
+    lex.next();
+    let indices = tensor_indices(lex, local)?;
+    lex.current.expect_symbol('}')?;
+    lex.next();
+
+    // Following indices use a base with the same height and depth
+    // as the first, so that indices are aligned vertically.
+    let mut nodes = Vec::new();
+    for (n, mut index) in indices.into_iter().enumerate() {
+        index.base = Some(Box::new(match n {
+            0 => base.clone(),
+            _ => ParseNode::Phantom(Phantom {
+                kind: PhantomKind::Vertical,
+                inner: vec![base.clone()],
+            }),
+        }));
+        nodes.push(ParseNode::Scripts(index));
+    }
+    if nodes.is_empty() {
+        nodes.push(base);
+    }
+
+    if let Some(pre) = pre {
+        let mut presuperscript = None;
+        let mut presubscript = None;
+        for index in pre {
+            if let Some(sup) = index.superscript {
+                if presuperscript.is_some() {
+                    return Err(ParseError::ExcessiveSuperscripts);
+                }
+                presuperscript = Some(sup);
+            }
+            if let Some(sub) = index.subscript {
+                if presubscript.is_some() {
+                    return Err(ParseError::ExcessiveSubscripts);
+                }
+                presubscript = Some(sub);
+            }
+        }
+
+        let first = nodes.remove(0);
+        nodes.insert(
+            0,
+            ParseNode::Scripts(Scripts::with_prescripts(
+                first,
+                presuperscript,
+                presubscript,
+            )),
+        );
+    }
+
+    Ok(ParseNode::Group(nodes))
+}
+
+/// Parse a list of indices, such as `^a_b^c`, with one script per index.
+fn tensor_indices<'a>(lex: &mut Lexer<'a>, local: Style) -> ParseResult<'a, Vec<Scripts>> {
+    let mut indices = Vec::new();
+    loop {
+        lex.consume_whitespace();
+        let upper = match lex.current {
+            Token::Symbol('^') => true,
+            Token::Symbol('_') => false,
+            _ => break,
+        };
+        lex.next();
+
+        let index = Some(parse::required_argument(lex, local)?);
+        let (superscript, subscript) = match upper {
+            true => (index, None),
+            false => (None, index),
+        };
+        indices.push(Scripts {
+            base: None,
+            superscript,
+            subscript,
+            presuperscript: None,
+            presubscript: None,
+        });
+    }
+    Ok(indices)
+}
+
+fn single_or_group(mut nodes: Vec<ParseNode>) -> ParseNode {
+    match nodes.len() {
+        1 => nodes.pop().unwrap(),
+        _ => ParseNode::Group(nodes),
+    }
+}
@@ -1,6 +1,7 @@
 #![allow(dead_code)]
 use super::{
-    Alignment, ColorChange, Grid, HorizontalBox, Layout, LayoutNode, LayoutVariant, VerticalBox,
+    Alignment, ColorChange, Grid, HorizontalBox, Layout, LayoutNode, LayoutVariant, Stroke,
+    VerticalBox,
 };
 use crate::dimensions::*;
 use crate::parser::nodes;
@@ -248,3 +249,32 @@ pub fn color<'a>(layout: Layout<'a>, color: &nodes::Color) -> LayoutNode<'a> {
         }),
     }
 }
+
+/// Create a zero-width node which draws a line from `from` to `to`.  The node
+/// is meant to overlay the content which follows it.
+pub fn stroke<'a>(
+    from: (Length<Px>, Length<Px>),
+    to: (Length<Px>, Length<Px>),
+    thickness: Length<Px>,
+) -> LayoutNode<'a> {
+    LayoutNode {
+        width: Length::zero(),
+        height: max!(from.1, to.1, Length::zero()),
+        depth: min!(from.1, to.1, Length::zero()),
+        node: LayoutVariant::Stroke(Stroke {
+            from,
+            to,
+            thickness,
+        }),
+    }
+}
+
+/// Create an empty node, which takes up space but draws nothing.
+pub fn phantom<'a>(width: Length<Px>, height: Length<Px>, depth: Length<Px>) -> LayoutNode<'a> {
+    LayoutNode {
+        width,
+        height,
+        depth,
+        node: LayoutVariant::Phantom,
+    }
+}
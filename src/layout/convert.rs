@@ -16,22 +16,38 @@ pub trait AsLayoutNode<'f> {
 
 impl<'f> AsLayoutNode<'f> for Glyph<'f> {
     fn as_layout<'a>(&self, config: LayoutSettings<'a, 'f>) -> LayoutResult<LayoutNode<'f>> {
-        Ok(LayoutNode {
-            height: self.height().scaled(config),
-            width: self.advance.scaled(config),
-            depth: self.depth().scaled(config),
-            node: LayoutVariant::Glyph(LayoutGlyph {
-                font: self.font,
-                gid: self.gid,
-                size: Length::new(1.0, Em).scaled(config),
-                attachment: self.attachment.scaled(config),
-                italics: self.italics.scaled(config),
-                offset: Length::zero(),
-            }),
-        })
+        // Scripts use the alternates from the font which are designed for
+        // smaller sizes, when available.  Fallback fonts have no such alternates.
+        let alternate = config
+            .script_level()
+            .filter(|_| config.ctx.is_math_glyph(self))
+            .and_then(|level| config.ctx.script_alternate(self.gid, level));
+        match alternate {
+            Some(gid) => glyph_node(&config.ctx.glyph_from_gid(gid)?, config),
+            None => glyph_node(self, config),
+        }
     }
 }
 
+fn glyph_node<'a, 'f>(
+    glyph: &Glyph<'f>,
+    config: LayoutSettings<'a, 'f>,
+) -> LayoutResult<LayoutNode<'f>> {
+    Ok(LayoutNode {
+        height: glyph.height().scaled(config),
+        width: glyph.advance.scaled(config),
+        depth: glyph.depth().scaled(config),
+        node: LayoutVariant::Glyph(LayoutGlyph {
+            font: glyph.font.clone(),
+            gid: glyph.gid,
+            size: Length::new(1.0, Em).scaled(config),
+            attachment: glyph.attachment.scaled(config),
+            italics: glyph.italics.scaled(config),
+            offset: Length::zero(),
+        }),
+    })
+}
+
 impl<'f> AsLayoutNode<'f> for Rule {
     fn as_layout<'a>(&self, config: LayoutSettings<'a, 'f>) -> LayoutResult<LayoutNode<'f>> {
         Ok(LayoutNode {
@@ -103,6 +119,19 @@ impl<'a, 'f> LayoutSettings<'a, 'f> {
     pub fn to_font(&self, length: Length<Px>) -> Length<Font> {
         length / self.font_size * self.ctx.units_per_em
     }
+
+    /// The level of the `ssty` feature to use for the current style.
+    fn script_level(&self) -> Option<u8> {
+        if !self.script_alternates {
+            return None;
+        }
+
+        match self.style {
+            Style::Script | Style::ScriptCramped => Some(1),
+            Style::ScriptScript | Style::ScriptScriptCramped => Some(2),
+            _ => None,
+        }
+    }
 }
 pub trait Scaled {
     fn scaled(self, config: LayoutSettings) -> Length<Px>;
@@ -4,7 +4,9 @@ use log::warn;
 
 use super::builders;
 use super::convert::AsLayoutNode;
-use super::{Alignment, Layout, LayoutNode, LayoutSettings, LayoutVariant, Style};
+use super::{
+    Alignment, ColorChange, Layout, LayoutGlyph, LayoutNode, LayoutSettings, LayoutVariant, Style,
+};
 
 use super::convert::Scaled;
 use super::spacing::{atom_space, Spacing};
@@ -12,12 +14,14 @@ use crate::dimensions::*;
 use crate::environments::Array;
 use crate::error::{LayoutError, LayoutResult};
 use crate::font::{
-    kerning::{subscript_kern, superscript_kern},
-    AtomType, VariantGlyph,
+    dotless_codepoint, is_dotted, is_math_italic,
+    kerning::{presubscript_kern, presuperscript_kern, subscript_kern, superscript_kern},
+    AtomType, Glyph, TextFont, VariantGlyph,
 };
 use crate::layout;
 use crate::parser::nodes::{
-    Accent, BarThickness, Delimited, GenFraction, MathStyle, ParseNode, Radical, Scripts, Stack,
+    Accent, BarThickness, Cancel, CancelKind, Delimited, FrameColor, Framed, GenFraction, Lap,
+    LapKind, MathStyle, ParseNode, Phantom, PhantomKind, Radical, Raise, Scripts, Stack,
 };
 use crate::parser::symbols::Symbol;
 
@@ -36,6 +40,17 @@ fn layout_recurse<'a, 'f: 'a>(
     mut config: LayoutSettings<'a, 'f>,
     parent_next: AtomType,
 ) -> LayoutResult<Layout<'f>> {
+    // The branch of a `\mathchoice` can only be chosen once the style is known,
+    // after which it is spliced into the list so that spacing is unaffected.
+    if nodes
+        .iter()
+        .any(|node| matches!(node, ParseNode::MathChoice(_)))
+    {
+        let mut resolved = Vec::with_capacity(nodes.len());
+        resolve_choices(nodes, config.style, &mut resolved);
+        return layout_recurse(&resolved, config, parent_next);
+    }
+
     let mut layout = Layout::new();
     let mut prev = AtomType::Transparent;
 
@@ -75,16 +90,67 @@ fn layout_recurse<'a, 'f: 'a>(
             layout.add_node(kern!(horz: kern));
         }
 
-        prev = current;
+        // An italic correction only adds a kern, and a math version change adds
+        // nothing, so neither changes the spacing of the atoms on either side.
+        if !matches!(
+            node,
+            ParseNode::ItalicCorrection | ParseNode::MathVersion(_)
+        ) {
+            prev = current;
+        }
         match *node {
             ParseNode::Style(sty) => config.style = sty,
+            ParseNode::MathVersion(version) => config = config.with_math_version(version),
             _ => layout.dispatch(config.clone(), node, next)?,
         }
+
+        if let (ParseNode::Symbol(sym), Some(ParseNode::Symbol(next))) = (node, nodes.get(idx + 1))
+        {
+            layout.symbol_kern(*sym, *next, config)?;
+        }
     }
 
     Ok(layout.finalize())
 }
 
+/// The glyphs of the math font which meet where a base and a script are kerned.
+/// Glyphs from fallback and text fonts have no cut-ins in the `MATH` table, and
+/// their gids would name unrelated glyphs of the math font.
+fn cut_in_glyphs<'f>(
+    config: LayoutSettings<'_, 'f>,
+    base: Option<LayoutGlyph<'f>>,
+    script: Option<LayoutGlyph<'f>>,
+) -> LayoutResult<Option<(Glyph<'f>, Glyph<'f>)>> {
+    match (base, script) {
+        (Some(base), Some(script))
+            if base.font.ptr_eq(&config.ctx.font) && script.font.ptr_eq(&config.ctx.font) =>
+        {
+            let base = config.ctx.glyph_from_gid(base.gid)?;
+            let script = config.ctx.glyph_from_gid(script.gid)?;
+            Ok(Some((base, script)))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Replace each `\mathchoice` with the branch for the style in effect,
+/// following any style changes along the way.  Returns the final style.
+fn resolve_choices(nodes: &[ParseNode], mut style: Style, resolved: &mut Vec<ParseNode>) -> Style {
+    for node in nodes {
+        match *node {
+            ParseNode::MathChoice(ref choice) => {
+                style = resolve_choices(choice.branch(style), style, resolved);
+            }
+            ParseNode::Style(sty) => {
+                style = sty;
+                resolved.push(node.clone());
+            }
+            _ => resolved.push(node.clone()),
+        }
+    }
+    style
+}
+
 fn layout_node<'a, 'f: 'a>(node: &ParseNode, config: LayoutSettings<'a, 'f>) -> Layout<'f> {
     let mut layout = Layout::new();
     layout
@@ -93,6 +159,23 @@ fn layout_node<'a, 'f: 'a>(node: &ParseNode, config: LayoutSettings<'a, 'f>) ->
     layout.finalize()
 }
 
+/// Layout `i` or `j` without its dot, for use under an accent.  The `dtls`
+/// feature of the font is preferred, since Unicode only has dotless letters
+/// for the upright and italic alphabets.
+fn dotless<'a, 'f>(sym: Symbol, config: LayoutSettings<'a, 'f>) -> LayoutResult<Layout<'f>> {
+    let glyph = config.ctx.glyph(sym.codepoint)?;
+    let glyph = match config.ctx.substitute(b"dtls", glyph.gid) {
+        Some(gid) => config.ctx.glyph_from_gid(gid)?,
+        None => dotless_codepoint(sym.codepoint)
+            .and_then(|codepoint| config.ctx.glyph(codepoint).ok())
+            .unwrap_or(glyph),
+    };
+
+    let mut layout = Layout::new();
+    layout.add_node(glyph.as_layout(config)?);
+    Ok(layout.finalize())
+}
+
 impl<'f> Layout<'f> {
     fn dispatch<'a>(
         &mut self,
@@ -109,6 +192,22 @@ impl<'f> Layout<'f> {
             ParseNode::GenFraction(ref f) => self.frac(f, config)?,
             ParseNode::Stack(ref stack) => self.substack(stack, config)?,
             ParseNode::Array(ref arr) => self.array(arr, config)?,
+            ParseNode::Cancel(ref cancel) => self.cancel(cancel, config)?,
+            ParseNode::Framed(ref framed) => self.framed(framed, config)?,
+            ParseNode::Phantom(ref phantom) => self.phantom(phantom, config)?,
+            ParseNode::Lap(ref lap) => self.lap(lap, config)?,
+            ParseNode::Raise(ref raise) => self.raise(raise, config)?,
+            ParseNode::PoorBold(ref inner) => self.poor_bold(inner, config)?,
+            ParseNode::Text(ref text) => self.text(text, config)?,
+            ParseNode::ItalicCorrection => {
+                if let Some(glyph) = layout::last_glyph(&self.contents) {
+                    self.add_node(kern!(horz: glyph.italics));
+                }
+            }
+            ParseNode::MathChoice(ref choice) => {
+                let inner = layout_recurse(choice.branch(config.style), config, next)?;
+                self.add_node(inner.as_node())
+            }
 
             ParseNode::AtomChange(ref ac) => self.add_node(layout(&ac.inner, config)?.as_node()),
             ParseNode::Group(ref gp) => self.add_node(layout(gp, config)?.as_node()),
@@ -135,6 +234,51 @@ impl<'f> Layout<'f> {
         Ok(())
     }
 
+    /// Running text is set in the text font, with its own kerning and
+    /// ligatures.  Characters missing from the text font are taken from the
+    /// math font and its fallbacks.
+    fn text<'a>(&mut self, text: &str, config: LayoutSettings<'a, 'f>) -> LayoutResult<()> {
+        let math_font;
+        let font = match config.text_font {
+            Some(font) => font,
+            None => {
+                math_font = TextFont::new(config.ctx.font.clone());
+                &math_font
+            }
+        };
+
+        let codepoints: Vec<char> = text.chars().collect();
+        let gids: Vec<Option<u16>> = codepoints.iter().map(|&c| font.gid(c)).collect();
+        let mut previous = None;
+        let mut idx = 0;
+        while idx < codepoints.len() {
+            let gid = match gids[idx] {
+                Some(gid) => gid,
+                None => {
+                    let glyph = config.ctx.glyph(codepoints[idx])?;
+                    self.add_node(glyph.as_layout(config)?);
+                    previous = None;
+                    idx += 1;
+                    continue;
+                }
+            };
+
+            let run: Vec<u16> = gids[idx..].iter().map_while(|&gid| gid).collect();
+            let (gid, len) = font.ligature(&run).unwrap_or((gid, 1));
+            if let Some(left) = previous {
+                let kern = font.kerning(config.ctx, left, gid);
+                if !kern.is_zero() {
+                    self.add_node(kern!(horz: kern.scaled(config)));
+                }
+            }
+
+            self.add_node(font.glyph(config.ctx, gid)?.as_layout(config)?);
+            previous = Some(gid);
+            idx += len;
+        }
+        Ok(())
+    }
+
     fn largeop<'a>(&mut self, sym: Symbol, config: LayoutSettings<'a, 'f>) -> LayoutResult<()> {
         let glyph = config.ctx.glyph(sym.codepoint)?;
         if config.style > Style::Text {
@@ -159,10 +303,23 @@ impl<'f> Layout<'f> {
         // [ ] Bottom accents: vertical placement is directly below nucleus,
         //       no correction takes place.
         // [ ] WideAccent vs Accent: Don't expand Accent types.
-        let base = layout(&acc.nucleus, config.cramped())?;
-        let accent_variant = config
+        let base = match acc.nucleus[..] {
+            [ParseNode::Symbol(sym)] if is_dotted(sym.codepoint) => dotless(sym, config.cramped())?,
+            _ => layout(&acc.nucleus, config.cramped())?,
+        };
+        let mut accent_variant = config
             .ctx
             .horz_variant(acc.symbol.codepoint, config.to_font(base.width))?;
+
+        // Tall bases, such as capital letters, use a flattened accent if available.
+        if let VariantGlyph::Replacement(gid) = accent_variant {
+            let flattened = config.ctx.constants.flattened_accent_base_height;
+            if base.height > flattened.scaled(config) {
+                if let Some(flac) = config.ctx.substitute(b"flac", gid) {
+                    accent_variant = VariantGlyph::Replacement(flac);
+                }
+            }
+        }
         let accent = accent_variant.as_layout(config)?;
 
         // Attachment points for accent & base are calculated by
@@ -217,6 +374,225 @@ impl<'f> Layout<'f> {
         Ok(())
     }
 
+    fn cancel<'a>(&mut self, cancel: &Cancel, config: LayoutSettings<'a, 'f>) -> LayoutResult<()> {
+        let inner = layout(&cancel.inner, config)?;
+        let thickness = config.ctx.constants.fraction_rule_thickness.scaled(config);
+        let width = inner.width;
+        let top = inner.height;
+        let bottom = inner.depth;
+        let left = Length::zero();
+
+        match cancel.kind {
+            CancelKind::Negation => {
+                // Overlay a solidus centered on the relation, without
+                // changing the width of the relation.
+                let axis = config.ctx.constants.axis_height.scaled(config);
+                let slash = config.ctx.glyph('/')?.as_layout(config)?;
+                let slash_width = slash.width;
+
+                self.add_node(inner.as_node());
+                self.add_node(kern!(horz: -(width + slash_width) * 0.5));
+                self.add_node(slash.centered(axis));
+                self.add_node(kern!(horz: (width - slash_width) * 0.5));
+            }
+
+            CancelKind::Forward => {
+                self.add_node(builders::stroke((left, bottom), (width, top), thickness));
+                self.add_node(inner.as_node());
+            }
+
+            CancelKind::Backward => {
+                self.add_node(builders::stroke((left, top), (width, bottom), thickness));
+                self.add_node(inner.as_node());
+            }
+
+            CancelKind::Cross => {
+                self.add_node(builders::stroke((left, bottom), (width, top), thickness));
+                self.add_node(builders::stroke((left, top), (width, bottom), thickness));
+                self.add_node(inner.as_node());
+            }
+
+            CancelKind::To => {
+                // The arrow overshoots the top right corner so that the
+                // label at its tip does not collide with the content.
+                let overshoot = Length::new(0.2, Em).scaled(config);
+                let head = Length::new(0.25, Em).scaled(config);
+                let tip = (width + overshoot, top + overshoot);
+
+                // The barbs of the arrow head are the reversed direction
+                // of the arrow, rotated by 30 degrees either way.
+                let dx = tip.0 / Px;
+                let dy = (tip.1 - bottom) / Px;
+                let length = dx.hypot(dy);
+                let (ux, uy) = (-dx / length, -dy / length);
+                let (sin, cos) = std::f64::consts::FRAC_PI_6.sin_cos();
+                let barb = |sign: f64| {
+                    let sin = sin * sign;
+                    let x = head * (ux * cos - uy * sin);
+                    let y = head * (ux * sin + uy * cos);
+                    builders::stroke(tip, (tip.0 + x, tip.1 + y), thickness)
+                };
+
+                let label = match cancel.label {
+                    Some(ref label) => layout(label, config.superscript_variant())?,
+                    None => Layout::new(),
+                };
+
+                self.add_node(builders::stroke((left, bottom), tip, thickness));
+                self.add_node(barb(1.0));
+                self.add_node(barb(-1.0));
+                self.add_node(inner.as_node());
+                self.add_node(kern!(horz: overshoot));
+                self.add_node(vbox!(offset: -tip.1; label.as_node()));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn framed<'a>(&mut self, framed: &Framed, config: LayoutSettings<'a, 'f>) -> LayoutResult<()> {
+        let inner = layout(&framed.inner, config)?;
+        let padding = config.frame_padding.scaled(config);
+        let thickness = match framed.frame {
+            FrameColor::None => Length::zero(),
+            _ => config.frame_thickness.scaled(config),
+        };
+
+        let margin = padding + thickness;
+        let width = inner.width + margin * 2.0;
+        let height = inner.height + margin;
+        let depth = inner.depth - margin;
+
+        // The background and the frame are drawn first, and the cursor is moved
+        // back to the start so that the content is drawn on top of them.
+        if let Some(color) = framed.background {
+            self.add_node(LayoutNode {
+                width,
+                height,
+                depth,
+                node: LayoutVariant::Background(color),
+            });
+            self.add_node(kern!(horz: -width));
+        }
+
+        if framed.frame != FrameColor::None {
+            // Vertical rules are drawn from the top, so we lower them by the depth.
+            let side = || vbox!(offset: -depth; rule!(width: thickness, height: height - depth));
+            let frame = hbox!(
+                side(),
+                vbox!(offset: -depth;
+                    rule!(width: width - thickness * 2.0, height: thickness),
+                    kern!(vert: height - depth - thickness * 2.0),
+                    rule!(width: width - thickness * 2.0, height: thickness)
+                ),
+                side()
+            );
+
+            match framed.frame {
+                FrameColor::Color(color) => self.add_node(LayoutNode {
+                    width: frame.width,
+                    height: frame.height,
+                    depth: frame.depth,
+                    node: LayoutVariant::Color(ColorChange {
+                        color,
+                        inner: vec![frame],
+                    }),
+                }),
+                _ => self.add_node(frame),
+            }
+            self.add_node(kern!(horz: -width));
+        }
+
+        self.add_node(kern!(horz: margin));
+        self.add_node(inner.as_node());
+        self.add_node(kern!(horz: margin));
+
+        Ok(())
+    }
+
+    fn phantom<'a>(
+        &mut self,
+        phantom: &Phantom,
+        config: LayoutSettings<'a, 'f>,
+    ) -> LayoutResult<()> {
+        let inner = layout(&phantom.inner, config)?;
+        let zero = Length::zero();
+
+        let node = match phantom.kind {
+            PhantomKind::Full => builders::phantom(inner.width, inner.height, inner.depth),
+            PhantomKind::Horizontal => builders::phantom(inner.width, zero, zero),
+            PhantomKind::Vertical => builders::phantom(zero, inner.height, inner.depth),
+
+            // The content is still drawn relative to the baseline, so only
+            // the reported dimensions need to change.
+            PhantomKind::Smash { top, bottom } => {
+                let mut node = inner.as_node();
+                if top {
+                    node.height = zero;
+                }
+                if bottom {
+                    node.depth = zero;
+                }
+                node
+            }
+        };
+
+        self.add_node(node);
+        Ok(())
+    }
+
+    fn lap<'a>(&mut self, lap: &Lap, config: LayoutSettings<'a, 'f>) -> LayoutResult<()> {
+        let inner = layout(&lap.inner, config)?;
+        let align = match lap.kind {
+            LapKind::Left => Alignment::Right(inner.width),
+            LapKind::Right => Alignment::Left,
+            LapKind::Center => Alignment::Centered(inner.width),
+        };
+
+        self.add_node(hbox!(align: align; width: Length::zero(); inner.as_node()));
+        Ok(())
+    }
+
+    fn raise<'a>(&mut self, raise: &Raise, config: LayoutSettings<'a, 'f>) -> LayoutResult<()> {
+        let inner = layout(&raise.inner, config)?;
+
+        // A positive offset lowers the box, and the height and depth
+        // are adjusted so that the raised content is not clipped.
+        let mut node = hbox!(offset: -raise.shift.scaled(config); inner.as_node());
+        if let Some(height) = raise.height {
+            node.height = height.scaled(config);
+        }
+        if let Some(depth) = raise.depth {
+            node.depth = -depth.scaled(config);
+        }
+
+        self.add_node(node);
+        Ok(())
+    }
+
+    /// Emulate a bold weight by drawing the content three times with small
+    /// offsets, using the same offsets as `\pmb` from amsbsy.
+    fn poor_bold<'a>(
+        &mut self,
+        inner: &[ParseNode],
+        config: LayoutSettings<'a, 'f>,
+    ) -> LayoutResult<()> {
+        let inner = layout(inner, config)?;
+        let shift = Length::new(0.025, Em).scaled(config);
+        let raise = Length::new(0.0433, Em).scaled(config);
+        let width = inner.width;
+
+        self.add_node(hbox!(
+            kern!(horz: -shift),
+            inner.clone().as_node(),
+            kern!(horz: shift * 2.0 - width),
+            inner.clone().as_node(),
+            kern!(horz: -shift - width),
+            hbox!(offset: -raise; inner.as_node())
+        ));
+        Ok(())
+    }
+
     fn delimited<'a>(
         &mut self,
         delim: &Delimited,
@@ -282,6 +658,57 @@ impl<'f> Layout<'f> {
         Ok(())
     }
 
+    /// Kerning between a symbol, which was the last node added, and the symbol
+    /// which follows it.  An italic letter is given its italic correction before
+    /// an upright ordinary, opening, closing or punctuation symbol, while adjacent
+    /// upright symbols of those classes are kerned using the pair kerning from the
+    /// font.  Binary operators and relations are separated by math spacing instead,
+    /// so neither kerning applies next to them.
+    fn symbol_kern<'a>(
+        &mut self,
+        sym: Symbol,
+        next: Symbol,
+        config: LayoutSettings<'a, 'f>,
+    ) -> LayoutResult<()> {
+        let glyph = match self.contents.last().and_then(|node| node.is_symbol()) {
+            Some(glyph) => glyph,
+            None => return Ok(()),
+        };
+
+        let kerned = |atom_type| {
+            matches!(
+                atom_type,
+                AtomType::Alpha
+                    | AtomType::Ordinal
+                    | AtomType::Open
+                    | AtomType::Close
+                    | AtomType::Punctuation
+            )
+        };
+        if !kerned(next.atom_type) || is_math_italic(next.codepoint) {
+            return Ok(());
+        }
+
+        let kern = if is_math_italic(sym.codepoint) {
+            glyph.italics
+        } else if kerned(sym.atom_type) {
+            // The pair kerning of the math font says nothing of glyphs taken
+            // from its fallbacks.
+            let next_glyph = config.ctx.glyph(next.codepoint)?;
+            if !glyph.font.ptr_eq(&config.ctx.font) || !config.ctx.is_math_glyph(&next_glyph) {
+                return Ok(());
+            }
+            config.ctx.kerning(glyph.gid, next_glyph.gid).scaled(config)
+        } else {
+            return Ok(());
+        };
+
+        if !kern.is_zero() {
+            self.add_node(kern!(horz: kern));
+        }
+        Ok(())
+    }
+
     fn scripts<'a>(
         &mut self,
         scripts: &Scripts,
@@ -304,6 +731,10 @@ impl<'f> Layout<'f> {
             None => Layout::new(),
         };
 
+        if scripts.has_prescripts() {
+            self.prescripts(scripts, &base, config)?;
+        }
+
         // We use a different algoirthm for handling scripts for operators with limits.
         // This is where he handle Operators with limits.
         if let Some(ref b) = scripts.base {
@@ -341,17 +772,20 @@ impl<'f> Layout<'f> {
                             height = config.ctx.glyph(sym.codepoint)?.height().scaled(config);
                         }
                     }
-                    // Apply italics correction is base is a symbol
-                    else if let Some(base_sym) = base.is_symbol() {
-                        // Lookup font kerning of superscript is also a symbol
-                        if let Some(sup_sym) = sup.is_symbol() {
-                            let bg = config.ctx.glyph_from_gid(base_sym.gid)?;
-                            let sg = config.ctx.glyph_from_gid(sup_sym.gid)?;
+                    // Apply italics correction if base is a symbol
+                    else {
+                        if let Some(base_sym) = base.is_symbol() {
+                            sup_kern = base_sym.italics;
+                        }
+
+                        // Lookup font kerning between the glyphs which meet at the
+                        // top right corner of the base.
+                        if let Some((bg, sg)) =
+                            cut_in_glyphs(config, base.last_glyph(), sup.first_glyph())?
+                        {
                             let kern = superscript_kern(&bg, &sg, config.to_font(adjust_up))
                                 .scaled(config);
-                            sup_kern = base_sym.italics + kern;
-                        } else {
-                            sup_kern = base_sym.italics;
+                            sup_kern += kern;
                         }
                     }
                 }
@@ -391,17 +825,12 @@ impl<'f> Layout<'f> {
                     if AtomType::Operator(false) == b.atom_type() {
                         // This recently changed in LuaTeX.  See `nolimitsmode`.
                         // This needs to be the glyph information _after_ layout for base.
-                        sub_kern = -config
-                            .ctx
-                            .glyph_from_gid(base_sym.gid)?
-                            .italics
-                            .scaled(config);
+                        sub_kern = -base_sym.italics;
                     }
                 }
 
-                if let (Some(ssym), Some(bsym)) = (sub.is_symbol(), base.is_symbol()) {
-                    let bg = config.ctx.glyph_from_gid(bsym.gid)?;
-                    let sg = config.ctx.glyph_from_gid(ssym.gid)?;
+                if let Some((bg, sg)) = cut_in_glyphs(config, base.last_glyph(), sub.first_glyph())?
+                {
                     sub_kern +=
                         subscript_kern(&bg, &sg, config.to_font(adjust_down)).scaled(config);
                 }
@@ -447,6 +876,101 @@ impl<'f> Layout<'f> {
         Ok(())
     }
 
+    /// Pre-scripts are aligned to the right against the base, and are kerned
+    /// using the left corners of the base.  Since the base is a single glyph
+    /// in most uses (ie: `{}^{14}_6\mathrm{C}`) we avoid the special cases
+    /// used for the scripts to the right of the base.
+    fn prescripts<'a>(
+        &mut self,
+        scripts: &Scripts,
+        base: &Layout<'f>,
+        config: LayoutSettings<'a, 'f>,
+    ) -> LayoutResult<()> {
+        let mut sup = match scripts.presuperscript {
+            Some(ref sup) => layout(sup, config.superscript_variant())?,
+            None => Layout::new(),
+        };
+
+        let mut sub = match scripts.presubscript {
+            Some(ref sub) => layout(sub, config.subscript_variant())?,
+            None => Layout::new(),
+        };
+
+        let mut adjust_up = Length::zero();
+        let mut adjust_down = Length::zero();
+        if scripts.presuperscript.is_some() {
+            let shift_up = match config.style.is_cramped() {
+                true => config.ctx.constants.superscript_shift_up_cramped,
+                false => config.ctx.constants.superscript_shift_up,
+            };
+            adjust_up = max!(
+                shift_up.scaled(config),
+                base.height
+                    - config
+                        .ctx
+                        .constants
+                        .superscript_baseline_drop_max
+                        .scaled(config),
+                config.ctx.constants.superscript_bottom_min.scaled(config) - sup.depth
+            );
+        }
+
+        if scripts.presubscript.is_some() {
+            adjust_down = max!(
+                config.ctx.constants.subscript_shift_down.scaled(config),
+                sub.height - config.ctx.constants.subscript_top_max.scaled(config),
+                config
+                    .ctx
+                    .constants
+                    .subscript_baseline_drop_min
+                    .scaled(config)
+                    - base.depth
+            );
+        }
+
+        if scripts.presuperscript.is_some() && scripts.presubscript.is_some() {
+            let sup_bot = adjust_up + sup.depth;
+            let sub_top = sub.height - adjust_down;
+            let gap_min = config.ctx.constants.sub_superscript_gap_min.scaled(config);
+            if sup_bot - sub_top < gap_min {
+                let adjust = (gap_min - sup_bot + sub_top) * 0.5;
+                adjust_up += adjust;
+                adjust_down += adjust;
+            }
+        }
+
+        // The cut-ins are placed between the scripts and the base.
+        let edge = base.first_glyph();
+        if let Some((bg, sg)) = cut_in_glyphs(config, edge.clone(), sup.last_glyph())? {
+            let kern = presuperscript_kern(&bg, &sg, config.to_font(adjust_up)).scaled(config);
+            sup.contents.push(kern!(horz: kern));
+            sup.width += kern;
+        }
+        if let Some((bg, sg)) = cut_in_glyphs(config, edge, sub.last_glyph())? {
+            let kern = presubscript_kern(&bg, &sg, config.to_font(adjust_down)).scaled(config);
+            sub.contents.push(kern!(horz: kern));
+            sub.width += kern;
+        }
+
+        let width = max(sup.width, sub.width);
+        let mut contents = builders::VBox::new();
+        if scripts.presuperscript.is_some() {
+            let corrected_adjust = adjust_up - sub.height + adjust_down;
+            contents
+                .add_node(hbox!(align: Alignment::Right(sup.width); width: width; sup.as_node()));
+            contents.add_node(kern!(vert: corrected_adjust));
+        }
+
+        contents.set_offset(adjust_down);
+        if scripts.presubscript.is_some() {
+            contents
+                .add_node(hbox!(align: Alignment::Right(sub.width); width: width; sub.as_node()));
+        }
+
+        self.add_node(contents.build());
+        Ok(())
+    }
+
     fn operator_limits<'a>(
         &mut self,
         base: Layout<'f>,
@@ -24,8 +24,9 @@ pub mod spacing;
 use serde_derive::{Deserialize, Serialize};
 
 use crate::dimensions::*;
-use crate::font::{FontContext, MathFont};
+use crate::font::{FontContext, FontRef, TextFont};
 use crate::parser::color::RGBA;
+use crate::parser::nodes::MathVersion;
 use std::cmp::{max, min};
 use std::collections::BTreeMap;
 use std::fmt;
@@ -89,6 +90,17 @@ impl<'f> Layout<'f> {
         }
         self.contents[0].is_symbol()
     }
+
+    /// The glyph on the left edge of the content, used to kern pre-scripts
+    /// and the base of a superscript or subscript against each other.
+    fn first_glyph(&self) -> Option<LayoutGlyph<'f>> {
+        self.is_symbol().or_else(|| first_glyph(&self.contents))
+    }
+
+    /// The glyph on the right edge of the content.  See `first_glyph`.
+    fn last_glyph(&self) -> Option<LayoutGlyph<'f>> {
+        self.is_symbol().or_else(|| last_glyph(&self.contents))
+    }
 }
 
 #[derive(Clone)]
@@ -106,8 +118,12 @@ pub enum LayoutVariant<'f> {
     VerticalBox(VerticalBox<'f>),
     Glyph(LayoutGlyph<'f>),
     Color(ColorChange<'f>),
+    Stroke(Stroke),
+    Background(RGBA),
     Rule,
     Kern,
+    /// Space with the dimensions of the node, which draws nothing.
+    Phantom,
 }
 
 #[derive(Clone)]
@@ -116,6 +132,15 @@ pub struct ColorChange<'f> {
     pub inner: Vec<LayoutNode<'f>>,
 }
 
+/// A straight line between two points.  The points are relative to the
+/// baseline at the left edge of the node, with `y` increasing upwards.
+#[derive(Clone, Copy, Debug)]
+pub struct Stroke {
+    pub from: (Length<Px>, Length<Px>),
+    pub to: (Length<Px>, Length<Px>),
+    pub thickness: Length<Px>,
+}
+
 #[derive(Clone)]
 pub struct Grid<'f> {
     pub contents: BTreeMap<(usize, usize), LayoutNode<'f>>,
@@ -139,14 +164,14 @@ pub struct VerticalBox<'f> {
     pub alignment: Alignment,
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone)]
 pub struct LayoutGlyph<'f> {
     pub gid: u16,
     pub size: Length<Px>,
     pub offset: Length<Px>,
     pub attachment: Length<Px>,
     pub italics: Length<Px>,
-    pub font: &'f MathFont,
+    pub font: FontRef<'f>,
 }
 
 #[allow(dead_code)]
@@ -213,6 +238,8 @@ impl<'f> fmt::Debug for LayoutNode<'f> {
             LayoutVariant::VerticalBox(ref vb) => write!(f, "VBox({:?})", vb.contents),
             LayoutVariant::Glyph(ref gly) => write!(f, "Glyph({:?})", gly),
             LayoutVariant::Rule => write!(f, "Rule()"),
+            LayoutVariant::Stroke(ref stroke) => write!(f, "{:?}", stroke),
+            LayoutVariant::Background(color) => write!(f, "Background({:?})", color),
             LayoutVariant::Kern => {
                 let kern = if self.width.is_zero() {
                     self.height
@@ -223,6 +250,11 @@ impl<'f> fmt::Debug for LayoutNode<'f> {
                 write!(f, "Kern({:.1})", kern)
             }
             LayoutVariant::Color(ref clr) => write!(f, "Color({:?}, {:?})", clr.color, clr.inner),
+            LayoutVariant::Phantom => write!(
+                f,
+                "Phantom({:.1}, {:.1}, {:.1})",
+                self.width, self.height, self.depth
+            ),
         }
     }
 }
@@ -249,9 +281,21 @@ impl<'f> LayoutNode<'f> {
         self
     }
 
+    fn edge_glyph(
+        &self,
+        edge: fn(&[LayoutNode<'f>]) -> Option<LayoutGlyph<'f>>,
+    ) -> Option<LayoutGlyph<'f>> {
+        match self.node {
+            LayoutVariant::Glyph(ref gly) => Some(gly.clone()),
+            LayoutVariant::HorizontalBox(ref hb) if hb.offset.is_zero() => edge(&hb.contents),
+            LayoutVariant::Color(ref clr) => edge(&clr.inner),
+            _ => None,
+        }
+    }
+
     fn is_symbol(&self) -> Option<LayoutGlyph<'f>> {
         match self.node {
-            LayoutVariant::Glyph(gly) => Some(gly),
+            LayoutVariant::Glyph(ref gly) => Some(gly.clone()),
             LayoutVariant::HorizontalBox(ref hb) => is_symbol(&hb.contents),
             LayoutVariant::VerticalBox(ref vb) => is_symbol(&vb.contents),
             LayoutVariant::Color(ref clr) => is_symbol(&clr.inner),
@@ -268,6 +312,24 @@ pub fn is_symbol<'a, 'b: 'a>(contents: &'a [LayoutNode<'b>]) -> Option<LayoutGly
     contents[0].is_symbol()
 }
 
+/// Find the first glyph of a horizontal list, skipping any kerns.  Glyphs which
+/// are shifted from the baseline, such as those in a vertical box, are ignored.
+pub fn first_glyph<'a, 'b: 'a>(contents: &'a [LayoutNode<'b>]) -> Option<LayoutGlyph<'b>> {
+    contents
+        .iter()
+        .find(|node| !matches!(node.node, LayoutVariant::Kern))
+        .and_then(|node| node.edge_glyph(first_glyph))
+}
+
+/// Find the last glyph of a horizontal list.  See `first_glyph`.
+pub fn last_glyph<'a, 'b: 'a>(contents: &'a [LayoutNode<'b>]) -> Option<LayoutGlyph<'b>> {
+    contents
+        .iter()
+        .rev()
+        .find(|node| !matches!(node.node, LayoutVariant::Kern))
+        .and_then(|node| node.edge_glyph(last_glyph))
+}
+
 /// Display styles which are used in scaling glyphs.  The associated
 /// methods are taken from pg.441 from the TeXBook
 #[allow(dead_code)]
@@ -355,20 +417,74 @@ impl Style {
 
 #[derive(Copy, Clone)]
 pub struct LayoutSettings<'a, 'f> {
+    /// The font context of the current math version.
     pub ctx: &'a FontContext<'f>,
+    /// The font context given to `new`, which holds every math version.
+    pub math_fonts: &'a FontContext<'f>,
     pub font_size: Scale<Px, Em>,
     pub style: Style,
+
+    /// The space between the content and the frame of `\boxed`,
+    /// similar to `\fboxsep` in LaTeX.
+    pub frame_padding: Length<Em>,
+
+    /// The thickness of the frame of `\boxed`, similar to `\fboxrule` in LaTeX.
+    pub frame_thickness: Length<Em>,
+
+    /// Use the `ssty` alternates from the font for glyphs in scripts.
+    pub script_alternates: bool,
+
+    /// The font for `\text` and operator names.  When absent, these are set
+    /// in the upright letters of the math font.
+    pub text_font: Option<&'a TextFont<'f>>,
 }
 
 impl<'a, 'f> LayoutSettings<'a, 'f> {
     pub fn new(ctx: &'a FontContext<'f>, font_size: f64, style: Style) -> Self {
         LayoutSettings {
             ctx,
+            math_fonts: ctx,
             font_size: Scale::new(font_size, Px, Em),
             style,
+            frame_padding: Length::new(0.3, Em),
+            frame_thickness: Length::new(0.04, Em),
+            script_alternates: true,
+            text_font: None,
+        }
+    }
+
+    pub fn with_frame(self, padding: Length<Em>, thickness: Length<Em>) -> Self {
+        LayoutSettings {
+            frame_padding: padding,
+            frame_thickness: thickness,
+            ..self
         }
     }
 
+    pub fn with_script_alternates(self, script_alternates: bool) -> Self {
+        LayoutSettings {
+            script_alternates,
+            ..self
+        }
+    }
+
+    pub fn with_text_font(self, text_font: &'a TextFont<'f>) -> Self {
+        LayoutSettings {
+            text_font: Some(text_font),
+            ..self
+        }
+    }
+
+    /// Switches to the math font of a version.  Without a bold math font,
+    /// the bold version keeps the normal font.
+    pub fn with_math_version(self, version: MathVersion) -> Self {
+        let ctx = match version {
+            MathVersion::Normal => self.math_fonts,
+            MathVersion::Bold => self.math_fonts.bold.as_deref().unwrap_or(self.math_fonts),
+        };
+        LayoutSettings { ctx, ..self }
+    }
+
     fn cramped(self) -> Self {
         LayoutSettings {
             style: self.style.cramped(),
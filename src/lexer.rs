@@ -3,6 +3,7 @@ use log::debug;
 use crate::dimensions::Unit;
 use crate::error::{ParseError, ParseResult};
 use crate::parser::color::RGBA;
+use std::collections::BTreeMap;
 use std::fmt;
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -52,7 +53,7 @@ impl<'a> Token<'a> {
     }
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Debug)]
 pub struct Lexer<'a> {
     pub input: &'a str,
 
@@ -64,6 +65,10 @@ pub struct Lexer<'a> {
 
     /// The token currently being processed.
     pub current: Token<'a>,
+
+    /// Colors defined with `\definecolor`, these are available
+    /// for the remainder of the input.
+    pub colors: BTreeMap<&'a str, RGBA>,
 }
 
 impl<'a> Lexer<'a> {
@@ -75,6 +80,7 @@ impl<'a> Lexer<'a> {
             pos: 0,
             prev_pos: 0,
             current: Token::EOF,
+            colors: BTreeMap::new(),
         };
 
         lex.next();
@@ -154,13 +160,46 @@ impl<'a> Lexer<'a> {
         Token::Command(&self.input[start..end])
     }
 
-    /// This method will parse a dimension.  It assumes
-    /// that the lexer is currently pointed to the first valid
+    /// This method will parse a dimension, such as `-1.5em`, `3 mu` or `12px`.
+    /// It assumes that the lexer is currently pointed to the first valid
     /// character in a dimension.  So it may be necessary to
-    /// consume_whitespace() prior to using this method.
+    /// consume_whitespace() prior to using this method.  If there is no
+    /// number to be parsed, then `None` is returned without advancing.
     pub fn dimension(&mut self) -> ParseResult<'a, Option<Unit>> {
-        // utter crap, rewrite.
-        unimplemented!()
+        let (sign, rest) = match self.current {
+            Token::Symbol('-') => (-1.0, self.input[self.pos..].trim_start()),
+            Token::Symbol('+') => (1.0, self.input[self.pos..].trim_start()),
+            _ => (1.0, &self.input[self.prev_pos..]),
+        };
+
+        let len = rest
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .unwrap_or(rest.len());
+        if len == 0 {
+            return Ok(None);
+        }
+
+        let value = match rest[..len].parse::<f64>() {
+            Ok(value) => sign * value,
+            Err(_) => return Err(ParseError::UnrecognizedDimension),
+        };
+
+        // TeX allows for spaces between the number and the unit.
+        let unit = rest[len..].trim_start();
+        let dimension = if unit.starts_with("em") {
+            Unit::Em(value)
+        } else if unit.starts_with("mu") {
+            Unit::Em(value / 18.0)
+        } else if unit.starts_with("px") {
+            Unit::Px(value)
+        } else {
+            return Err(ParseError::UnrecognizedDimension);
+        };
+
+        // Place cursor immediately after the unit
+        self.pos = self.input.len() - unit.len() + 2;
+        self.next();
+        Ok(Some(dimension))
     }
 
     /// Expect to find an {<inner>}, and return <inner>
@@ -202,13 +241,24 @@ impl<'a> Lexer<'a> {
         result
     }
 
-    // Match a valid Color.  A color is defined as either:
-    //   1. Alphabetic name for a valid CSS color.
-    //   2. #RRGGBB (that is a # followed by 6 digits)
-    //   3. #RRGGBBAA (that is a # followed by 8 digits)
+    /// Match a decimal number without a sign, such as `12` or `12.5`.  This
+    /// method will return an empty string if there is no number.
+    pub fn number(&mut self) -> &'a str {
+        let is_number = |c: char| c.is_ascii_digit() || c == '.';
+        let start = match self.current {
+            Token::Symbol(c) if is_number(c) => self.pos - c.len_utf8(),
+            _ => return "",
+        };
 
-    pub fn color(&mut self) -> ParseResult<'a, RGBA> {
-        unimplemented!()
+        while let Some(c) = self.current_char() {
+            if !is_number(c) {
+                break;
+            }
+            self.pos += c.len_utf8()
+        }
+        let result = &self.input[start..self.pos];
+        self.next();
+        result
     }
 
     fn next_char(&mut self) -> Option<char> {
@@ -239,6 +289,7 @@ impl<'a> fmt::Display for Token<'a> {
 
 #[cfg(test)]
 mod tests {
+    use crate::error::ParseError;
     use crate::lexer::{Lexer, Token};
 
     #[test]
@@ -312,24 +363,29 @@ mod tests {
         assert_alphanumeric!("abc!", "abc");
     }
 
-    // #[test]
-    // fn lex_dimension() {
-    //     use dimensions::Unit;
-    //     macro_rules! assert_dim {
-    //         ($input:expr, $result:expr) => (
-    //             let mut _l = Lexer::new($input);
-    //             assert_eq!(_l.dimension().unwrap(), Some(Unit::Px($result)));
-    //         )
-    //     }
-
-    //     assert_dim!(r"123 abc", 123.0);
-    //     assert_dim!(r"1.23 abc", 1.23);
-    //     assert_dim!(r"- 1.23 123", -1.23);
-    //     assert_dim!(r"+1.34 134", 1.34);
-    //     assert_dim!("-   12", -12.0);
-    //     assert_dim!("+   12", 12.0);
-    //     assert_dim!("-  .12", -0.12);
-    //     assert_dim!("00.123000", 0.123);
-    //     assert_dim!("001.10000", 1.1);
-    // }
+    #[test]
+    fn lex_dimension() {
+        use crate::dimensions::Unit;
+        macro_rules! assert_dim {
+            ($input:expr, $result:expr) => {
+                let mut _l = Lexer::new($input);
+                assert_eq!(_l.dimension(), $result);
+            };
+        }
+
+        assert_dim!(r"123em abc", Ok(Some(Unit::Em(123.0))));
+        assert_dim!(r"1.23 em abc", Ok(Some(Unit::Em(1.23))));
+        assert_dim!(r"- 1.23em 123", Ok(Some(Unit::Em(-1.23))));
+        assert_dim!(r"+1.5px 134", Ok(Some(Unit::Px(1.5))));
+        assert_dim!("-  .5em", Ok(Some(Unit::Em(-0.5))));
+        assert_dim!("00.125000em", Ok(Some(Unit::Em(0.125))));
+        assert_dim!("18mu", Ok(Some(Unit::Em(1.0))));
+        assert_dim!("abc", Ok(None));
+        assert_dim!("12", Err(ParseError::UnrecognizedDimension));
+        assert_dim!("12cm", Err(ParseError::UnrecognizedDimension));
+
+        let mut lex = Lexer::new(r"1em\alpha");
+        lex.dimension().unwrap();
+        assert_eq!(lex.current, Token::Command("alpha"));
+    }
 }
@@ -22,6 +22,25 @@ macro_rules! alt {
     )
 }
 
+/// Defines a function which looks up a name in a table, together with a
+/// constant listing every name in the table, so that the names can be
+/// enumerated as well as matched.
+macro_rules! name_table {
+    ($(#[$meta:meta])* $vis:vis fn $func:ident($name:ident) -> $ty:ty, $names:ident {
+        $($key:literal => $value:expr,)*
+    }) => {
+        $(#[$meta])*
+        $vis fn $func($name: &str) -> Option<$ty> {
+            Some(match $name {
+                $($key => $value,)*
+                _ => return None,
+            })
+        }
+
+        $vis const $names: &[&str] = &[$($key,)*];
+    };
+}
+
 // ----------------------
 // Testing related Macros
 // ----------------------
@@ -11,6 +11,69 @@ impl RGBA {
     pub fn from_name(name: &str) -> Option<RGBA> {
         COLOR_MAP.get(name).cloned()
     }
+
+    /// Parse a hexadecimal color of the form `RRGGBB` or `RRGGBBAA`.
+    pub fn from_hex(hex: &str) -> Option<RGBA> {
+        // `from_str_radix` would also accept a sign before each channel.
+        if !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return None;
+        }
+
+        let channel = |idx: usize| {
+            hex.get(2 * idx..2 * idx + 2)
+                .and_then(|c| u8::from_str_radix(c, 16).ok())
+        };
+
+        match hex.len() {
+            6 => Some(RGBA(channel(0)?, channel(1)?, channel(2)?, 0xff)),
+            8 => Some(RGBA(channel(0)?, channel(1)?, channel(2)?, channel(3)?)),
+            _ => None,
+        }
+    }
+
+    /// Parse a color specification for one of the xcolor models:
+    ///  - `rgb`: three values from 0 to 1 (ie: `0.1,0.2,0.3`).
+    ///  - `RGB`: three values from 0 to 255 (ie: `25,51,76`).
+    ///  - `HTML`: six hexadecimal digits (ie: `19334C`).
+    ///  - `gray`: a single value from 0 to 1 (ie: `0.5`).
+    pub fn from_model(model: &str, spec: &str) -> Option<RGBA> {
+        let values: Vec<&str> = spec.split(',').map(str::trim).collect();
+        let unit = |v: &str| -> Option<u8> {
+            let v: f64 = v.parse().ok()?;
+            if !(0.0..=1.0).contains(&v) {
+                return None;
+            }
+            Some((v * 255.0).round() as u8)
+        };
+
+        match (model, values.as_slice()) {
+            ("rgb", &[r, g, b]) => Some(RGBA(unit(r)?, unit(g)?, unit(b)?, 0xff)),
+            ("RGB", &[r, g, b]) => Some(RGBA(
+                r.parse().ok()?,
+                g.parse().ok()?,
+                b.parse().ok()?,
+                0xff,
+            )),
+            ("HTML", &[hex]) if hex.len() == 6 => RGBA::from_hex(hex),
+            ("gray", &[v]) => {
+                let v = unit(v)?;
+                Some(RGBA(v, v, v, 0xff))
+            }
+            _ => None,
+        }
+    }
+
+    /// Mix two colors as xcolor does for `red!30!blue`, where `ratio` is the
+    /// portion of `self` in the result.
+    pub fn mix(self, other: RGBA, ratio: f64) -> RGBA {
+        let channel = |a: u8, b: u8| (a as f64 * ratio + b as f64 * (1.0 - ratio)).round() as u8;
+        RGBA(
+            channel(self.0, other.0),
+            channel(self.1, other.1),
+            channel(self.2, other.2),
+            channel(self.3, other.3),
+        )
+    }
 }
 
 static COLOR_MAP: phf::Map<&'static str, RGBA> = phf_map! {
@@ -165,3 +228,42 @@ static COLOR_MAP: phf::Map<&'static str, RGBA> = phf_map! {
     "phantom" => RGBA(0x00,0x00,0x00,0x00),
     "transparent" => RGBA(0x00,0x00,0x00,0x00),
 };
+
+#[cfg(test)]
+mod tests {
+    use super::RGBA;
+
+    #[test]
+    fn color_models() {
+        assert_eq!(RGBA::from_hex("ff8000"), Some(RGBA(0xff, 0x80, 0x00, 0xff)));
+        assert_eq!(
+            RGBA::from_hex("ff800080"),
+            Some(RGBA(0xff, 0x80, 0x00, 0x80))
+        );
+        assert_eq!(RGBA::from_hex("ff80"), None);
+        assert_eq!(RGBA::from_hex("gg0000"), None);
+        assert_eq!(RGBA::from_hex("+f+f+f"), None);
+        assert_eq!(RGBA::from_hex("-1ff00"), None);
+
+        assert_eq!(
+            RGBA::from_model("rgb", "1, 0.5, 0"),
+            Some(RGBA(0xff, 0x80, 0x00, 0xff))
+        );
+        assert_eq!(
+            RGBA::from_model("RGB", "255,128,0"),
+            Some(RGBA(0xff, 0x80, 0x00, 0xff))
+        );
+        assert_eq!(
+            RGBA::from_model("HTML", "FF8000"),
+            Some(RGBA(0xff, 0x80, 0x00, 0xff))
+        );
+        assert_eq!(RGBA::from_model("gray", "0"), Some(RGBA(0, 0, 0, 0xff)));
+        assert_eq!(RGBA::from_model("rgb", "2,0,0"), None);
+        assert_eq!(RGBA::from_model("cmyk", "0,0,0,1"), None);
+
+        let red = RGBA(0xff, 0, 0, 0xff);
+        let blue = RGBA(0, 0, 0xff, 0xff);
+        assert_eq!(red.mix(blue, 0.5), RGBA(0x80, 0, 0x80, 0xff));
+        assert_eq!(red.mix(blue, 1.0), red);
+    }
+}
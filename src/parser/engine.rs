@@ -1,12 +1,12 @@
 use crate::dimensions::*;
 use crate::environments::Environment;
 use crate::error::{ParseError, ParseResult};
-use crate::font::{style_symbol, AtomType, Style};
+use crate::font::{is_math_alphanumeric, style_symbol, AtomType, MathStyleConvention, Style};
 use crate::functions::get_command;
 use crate::lexer::{Lexer, Token};
 use crate::parser::{
     color::RGBA,
-    nodes::{Accent, Delimited, ParseNode, Scripts},
+    nodes::{Accent, Color, Delimited, ParseNode, Scripts},
     symbols::Symbol,
 };
 
@@ -23,7 +23,7 @@ pub fn expression_until_opt<'a>(
             break;
         }
 
-        let node = alt!(
+        let mut node = alt!(
             command(lex, local),
             group(lex, local),
             symbol(lex, local),
@@ -33,11 +33,35 @@ pub fn expression_until_opt<'a>(
         // Handle commands that may change the state of the parser
         // ie: fontstyle changes.
         if node.is_none() {
-            if let Some(mut nodes) = state_change(lex, local)? {
-                ml.append(&mut nodes);
+            if let Some(node) = color_change(lex, local, end)? {
+                ml.push(node);
                 continue;
             }
 
+            // Color definitions don't produce any nodes, so there is nothing
+            // for a script to be attached to.
+            if color_definition(lex, local)? {
+                lex.consume_whitespace();
+                if starts_script(lex.current) {
+                    return Err(ParseError::ScriptAfterColorDefinition);
+                }
+                continue;
+            }
+
+            // The argument of a font change is the base of any scripts which
+            // follow, as in `\mathrm{T}^2`.
+            if let Some(mut nodes) = state_change(lex, local)? {
+                lex.consume_whitespace();
+                if !starts_script(lex.current) {
+                    ml.append(&mut nodes);
+                    continue;
+                }
+                node = Some(match nodes.len() {
+                    1 => nodes.pop().unwrap(),
+                    _ => ParseNode::Group(nodes),
+                });
+            }
+
             // At this point, if the current `Token` is a Command,
             // then it must be an unrecognized Command.
             if let Token::Command(cmd) = lex.current {
@@ -48,6 +72,10 @@ pub fn expression_until_opt<'a>(
         // Post-fix operators are handled as a special case since they need
         // access to the currently processed node.
         let node = postfix(lex, local, node)?;
+        let node = match node {
+            Some(node) => Some(prescripts(lex, local, end, node)?),
+            None => None,
+        };
 
         // If at this point, we still haven't processed a node then we must have
         // an unrecognized symbol (perhaps from non-english, non-greek).
@@ -89,6 +117,10 @@ fn postfix<'a>(
 ) -> ParseResult<'a, Option<ParseNode>> {
     let mut superscript = None;
     let mut subscript = None;
+
+    // Primes are placed in a superscript, and a superscript which
+    // immediately follows is merged with them, so `f'^2` is `f^{\prime 2}`.
+    let mut primed = false;
     loop {
         lex.consume_whitespace();
         match lex.current {
@@ -102,11 +134,34 @@ fn postfix<'a>(
             }
             Token::Symbol('^') => {
                 lex.next();
-                // If we already have a superscript, bail.
+                match superscript {
+                    Some(ref mut primes) if primed => {
+                        primed = false;
+                        primes.append(&mut required_argument(lex, local)?);
+                    }
+                    // If we already have a superscript, bail.
+                    Some(_) => return Err(ParseError::ExcessiveSuperscripts),
+                    None => superscript = Some(required_argument(lex, local)?),
+                }
+            }
+            Token::Symbol(c) if prime_count(c).is_some() => {
+                if superscript.is_some() {
+                    return Err(ParseError::ExcessiveSuperscripts);
+                }
+                superscript = Some(primes(lex, local));
+                primed = true;
+            }
+            Token::Symbol(c) if unicode_subscript(c).is_some() => {
+                if subscript.is_some() {
+                    return Err(ParseError::ExcessiveSubscripts);
+                }
+                subscript = Some(unicode_script(lex, local, unicode_subscript));
+            }
+            Token::Symbol(c) if unicode_superscript(c).is_some() => {
                 if superscript.is_some() {
                     return Err(ParseError::ExcessiveSuperscripts);
                 }
-                superscript = Some(required_argument(lex, local)?);
+                superscript = Some(unicode_script(lex, local, unicode_superscript));
             }
             Token::Command("limits") => {
                 lex.next();
@@ -135,12 +190,55 @@ fn postfix<'a>(
             base: prev.map(|b| Box::new(b)),
             superscript: superscript,
             subscript: subscript,
+            presuperscript: None,
+            presubscript: None,
         })))
     } else {
         Ok(prev)
     }
 }
 
+/// Scripts attached to an empty group, as in `{}^{14}_6\mathrm{C}`, are
+/// taken to be the pre-scripts of the atom which follows.  Any other node
+/// is returned unchanged.
+fn prescripts<'a>(
+    lex: &mut Lexer<'a>,
+    local: Style,
+    end: Option<Token>,
+    node: ParseNode,
+) -> ParseResult<'a, ParseNode> {
+    let scripts = match node {
+        ParseNode::Scripts(scripts)
+            if !scripts.has_prescripts()
+                && scripts.base.as_deref() == Some(&ParseNode::Group(Vec::new())) =>
+        {
+            scripts
+        }
+        node => return Ok(node),
+    };
+
+    lex.consume_whitespace();
+    if Some(lex.current) == end || lex.current.ends_expression() {
+        return Ok(ParseNode::Scripts(scripts));
+    }
+
+    let base = match alt!(command(lex, local), group(lex, local), symbol(lex, local)) {
+        Some(base) => base,
+        None => match state_change(lex, local)? {
+            Some(mut nodes) if nodes.len() == 1 => nodes.pop().unwrap(),
+            Some(nodes) => ParseNode::Group(nodes),
+            None => return Ok(ParseNode::Scripts(scripts)),
+        },
+    };
+
+    let base = postfix(lex, local, Some(base))?.expect("postfix keeps its base");
+    Ok(ParseNode::Scripts(Scripts::with_prescripts(
+        base,
+        scripts.superscript,
+        scripts.subscript,
+    )))
+}
+
 /// Theses commands may change the state of the parser.  This includes
 /// font style and weight changes.
 pub fn state_change<'a>(
@@ -149,10 +247,18 @@ pub fn state_change<'a>(
 ) -> ParseResult<'a, Option<Vec<ParseNode>>> {
     use crate::font::Family;
     if let Token::Command(cmd) = lex.current {
+        // Symbols without a bold glyph are emboldened by overstriking.
+        if cmd == "boldsymbol" || cmd == "bm" {
+            lex.next();
+            let nodes = required_argument(lex, style.with_bold_symbol())?;
+            return Ok(Some(nodes.into_iter().map(bold_fallback).collect()));
+        }
+
         let new_style = match cmd {
             "mathbf" => style.with_bold(),
             "mathit" => style.with_italics(),
             "mathrm" => style.with_family(Family::Roman),
+            "mathup" => style.with_upright(),
             "mathscr" => style.with_family(Family::Script),
             "mathfrak" => style.with_family(Family::Fraktur),
             "mathbb" => style.with_family(Family::Blackboard),
@@ -169,6 +275,54 @@ pub fn state_change<'a>(
     Ok(None)
 }
 
+/// Replace symbols which have no bold glyph with a poor man's bold, as done by `\pmb`.
+fn bold_fallback(node: ParseNode) -> ParseNode {
+    match node {
+        ParseNode::Symbol(sym) if !is_math_alphanumeric(sym.codepoint) => {
+            ParseNode::PoorBold(vec![node])
+        }
+        ParseNode::Scripts(scripts) => ParseNode::Scripts(Scripts {
+            base: scripts.base.map(|base| Box::new(bold_fallback(*base))),
+            superscript: scripts
+                .superscript
+                .map(|sup| sup.into_iter().map(bold_fallback).collect()),
+            subscript: scripts
+                .subscript
+                .map(|sub| sub.into_iter().map(bold_fallback).collect()),
+            presuperscript: scripts
+                .presuperscript
+                .map(|sup| sup.into_iter().map(bold_fallback).collect()),
+            presubscript: scripts
+                .presubscript
+                .map(|sub| sub.into_iter().map(bold_fallback).collect()),
+        }),
+        ParseNode::Group(inner) => ParseNode::Group(inner.into_iter().map(bold_fallback).collect()),
+        ParseNode::Color(clr) => ParseNode::Color(Color {
+            color: clr.color,
+            inner: clr.inner.into_iter().map(bold_fallback).collect(),
+        }),
+        _ => node,
+    }
+}
+
+/// The `\color` command is a switch, similar to font changes in TeX.  That is,
+/// `\color{red} a + b` will color everything until the end of the enclosing group.
+/// For the argument form, see `\textcolor`.
+pub fn color_change<'a>(
+    lex: &mut Lexer<'a>,
+    local: Style,
+    end: Option<Token>,
+) -> ParseResult<'a, Option<ParseNode>> {
+    if lex.current != Token::Command("color") {
+        return Ok(None);
+    }
+
+    lex.next();
+    let color = color_argument(lex, local)?;
+    let inner = expression_until_opt(lex, local, end)?;
+    Ok(Some(ParseNode::Color(Color { color, inner })))
+}
+
 /// Parse a TeX command. These commands define the "primitive" commands for our
 /// typesetting system.  It tires to include a large portion of the TeX primitives,
 /// along with the most useful primitives you find from amsmath and LaTeX.
@@ -253,6 +407,14 @@ pub fn group<'a>(lex: &mut Lexer<'a>, local: Style) -> ParseResult<'a, Option<Pa
 pub fn symbol<'a>(lex: &mut Lexer<'a>, local: Style) -> ParseResult<'a, Option<ParseNode>> {
     match lex.current {
         Token::Command(cs) => {
+            if let Some(sym) = Symbol::from_upgreek_name(cs) {
+                lex.next();
+                return Ok(Some(symbol!(
+                    style_symbol(sym.codepoint, local.with_upright()),
+                    sym.atom_type
+                )));
+            }
+
             if let Some(sym) = Symbol::from_name(cs) {
                 lex.next();
                 match sym.atom_type {
@@ -269,6 +431,13 @@ pub fn symbol<'a>(lex: &mut Lexer<'a>, local: Style) -> ParseResult<'a, Option<P
                 Ok(None)
             }
         }
+        // Primes are handled as a postfix operator.
+        Token::Symbol(c) if prime_count(c).is_some() => Ok(None),
+        // Mathematical Alphanumeric characters are already styled.
+        Token::Symbol(c) if is_math_alphanumeric(c) => {
+            lex.next();
+            Ok(Some(symbol!(c, AtomType::Alpha)))
+        }
         Token::Symbol(c) => match codepoint_atom_type(c) {
             None => Ok(None),
             Some(sym) => {
@@ -302,7 +471,7 @@ pub fn required_argument<'a>(lex: &mut Lexer<'a>, local: Style) -> ParseResult<'
 }
 
 /// This method is similar to `required_argument`, but instead uses a custom parser.
-/// For instance, `\color{#012345}{<expression>}` uses a custom parser to parse
+/// For instance, `\textcolor{#012345}{<expression>}` uses a custom parser to parse
 /// the color token `#012345`.
 pub fn required_argument_with<'a, F, O>(
     lex: &mut Lexer<'a>,
@@ -389,24 +558,95 @@ pub fn expect_type<'a>(
     }
 }
 
-pub fn dimension<'a>(_: &mut Lexer<'a>, _: Style) -> ParseResult<'a, Unit> {
-    unimplemented!()
+pub fn dimension<'a>(lex: &mut Lexer<'a>, _: Style) -> ParseResult<'a, Unit> {
+    lex.dimension()?.ok_or(ParseError::UnrecognizedDimension)
 }
 
 /// Match a valid color token. Valid color tokens are:
 ///  - Ascii name for css color (ie: `red`).
+///  - A name defined by `\definecolor`.
 ///  - #RRGGBB (ie: `#ff0000` for red)
 ///  - #RRGGBBAA (ie: `#00000000` for transparent)
 ///  - `transparent`
-
-// TODO: implement parsing for other formats.
+///  - An xcolor mixture of the above (ie: `red!30!blue` or `red!30`).
 pub fn color<'a>(lex: &mut Lexer<'a>, _: Style) -> ParseResult<'a, RGBA> {
-    let color_str = lex.alphanumeric();
-    let color = RGBA::from_name(color_str)
-        .ok_or_else(|| ParseError::UnrecognizedColor(color_str.into()))?;
+    if lex.current == Token::Symbol('#') {
+        lex.next();
+        let hex = lex.alphanumeric();
+        return RGBA::from_hex(hex).ok_or(ParseError::UnrecognizedColor(hex));
+    }
+
+    let mut color = named_color(lex)?;
+
+    // Mixtures are evaluated from left to right, so that `red!50!blue!20`
+    // is the same as `{red!50!blue}!20!white`.
+    while lex.current == Token::Symbol('!') {
+        lex.next();
+        let percent = lex.number();
+        let ratio = match percent.parse::<f64>() {
+            Ok(p) if (0.0..=100.0).contains(&p) => p / 100.0,
+            _ => return Err(ParseError::UnrecognizedColor(percent)),
+        };
+
+        let other = if lex.current == Token::Symbol('!') {
+            lex.next();
+            named_color(lex)?
+        } else {
+            RGBA(0xff, 0xff, 0xff, 0xff)
+        };
+
+        color = color.mix(other, ratio);
+    }
+
     Ok(color)
 }
 
+fn named_color<'a>(lex: &mut Lexer<'a>) -> ParseResult<'a, RGBA> {
+    let name = lex.alphanumeric();
+    lex.colors
+        .get(name)
+        .cloned()
+        .or_else(|| RGBA::from_name(name))
+        .ok_or(ParseError::UnrecognizedColor(name))
+}
+
+/// Parse a color argument with an optional xcolor model, for example
+/// `{red}`, `[rgb]{0.1,0.2,0.3}` or `[HTML]{1A2B3C}`.
+pub fn color_argument<'a>(lex: &mut Lexer<'a>, local: Style) -> ParseResult<'a, RGBA> {
+    let model = optional_argument_with(lex, local, |lex, _| Ok(Some(lex.alphanumeric())))?;
+    match model {
+        Some(model) => {
+            let spec = lex.group()?;
+            RGBA::from_model(model, spec).ok_or(ParseError::UnrecognizedColor(spec))
+        }
+        None => required_argument_with(lex, local, color),
+    }
+}
+
+/// The `\definecolor` command, which defines a color without producing any
+/// nodes.  Returns true if a color was defined.
+pub fn color_definition<'a>(lex: &mut Lexer<'a>, local: Style) -> ParseResult<'a, bool> {
+    if lex.current != Token::Command("definecolor") {
+        return Ok(false);
+    }
+
+    lex.next();
+    define_color(lex, local)?;
+    Ok(true)
+}
+
+/// Parse `\definecolor{name}{model}{spec}`.  The lexer is expected to be
+/// positioned after `\definecolor`.  The color is stored in the lexer, so
+/// that it can be used by name in the remainder of the input.
+pub fn define_color<'a>(lex: &mut Lexer<'a>, _: Style) -> ParseResult<'a, ()> {
+    let name = lex.group()?.trim();
+    let model = lex.group()?.trim();
+    let spec = lex.group()?;
+    let color = RGBA::from_model(model, spec).ok_or(ParseError::UnrecognizedColor(spec))?;
+    lex.colors.insert(name, color);
+    Ok(())
+}
+
 pub fn environment_name<'a>(lex: &mut Lexer<'a>, _: Style) -> ParseResult<'a, Environment> {
     let name = lex.alphanumeric();
     Environment::try_from_str(name).ok_or(ParseError::Todo)
@@ -414,8 +654,16 @@ pub fn environment_name<'a>(lex: &mut Lexer<'a>, _: Style) -> ParseResult<'a, En
 
 /// This function is the API entry point for parsing tex.
 pub fn parse(input: &str) -> ParseResult<Vec<ParseNode>> {
+    parse_with_convention(input, MathStyleConvention::default())
+}
+
+/// Parse the input, where letters are styled according to the given convention.
+pub fn parse_with_convention(
+    input: &str,
+    convention: MathStyleConvention,
+) -> ParseResult<Vec<ParseNode>> {
     let mut lexer = Lexer::new(input);
-    let local = Style::new();
+    let local = Style::new().with_convention(convention);
     let parse_result = expression(&mut lexer, local)?;
     if lexer.current != Token::EOF {
         return Err(ParseError::UnexpectedEof(lexer.current.into()));
@@ -437,10 +685,142 @@ fn codepoint_atom_type(codepoint: char) -> Option<AtomType> {
         ',' | ';' => AtomType::Punctuation,
         '|' => AtomType::Fence,
         '/' | '@' | '.' | '"' => AtomType::Alpha,
+        _ => match Symbol::from_codepoint(codepoint) {
+            Some(sym) => sym.atom_type,
+            None if is_ordinary_letter(codepoint) => AtomType::Alpha,
+            None => return None,
+        },
+    })
+}
+
+/// Letters and pictographs unknown to math fonts, such as CJK ideographs or
+/// emoji, are ordinary symbols which may be found in a fallback font.
+fn is_ordinary_letter(codepoint: char) -> bool {
+    let pictograph = matches!(codepoint, '\u{2600}'..='\u{27BF}' | '\u{1F000}'..='\u{1FAFF}');
+    (codepoint.is_alphanumeric() || pictograph) && !starts_script(Token::Symbol(codepoint))
+}
+
+/// Whether the token begins one of the scripts handled by `postfix`.
+fn starts_script(token: Token) -> bool {
+    match token {
+        Token::Symbol('^') | Token::Symbol('_') => true,
+        Token::Symbol(c) => {
+            prime_count(c).is_some()
+                || unicode_superscript(c).is_some()
+                || unicode_subscript(c).is_some()
+        }
+        _ => false,
+    }
+}
+
+/// The number of primes represented by a character.  Both apostrophes
+/// and the Unicode prime characters are accepted.
+fn prime_count(codepoint: char) -> Option<usize> {
+    match codepoint {
+        '\'' | '\u{2032}' => Some(1),
+        '\u{2033}' => Some(2),
+        '\u{2034}' => Some(3),
+        '\u{2057}' => Some(4),
+        _ => None,
+    }
+}
+
+/// Collect a run of primes, such as `''`, and combine them into the
+/// fewest prime characters (`′`, `″`, `‴` and `⁗`).
+fn primes(lex: &mut Lexer, local: Style) -> Vec<ParseNode> {
+    let mut count = 0;
+    while let Token::Symbol(c) = lex.current {
+        match prime_count(c) {
+            Some(n) => count += n,
+            None => break,
+        }
+        lex.next();
+    }
+
+    let mut contents = Vec::new();
+    while count > 0 {
+        let (codepoint, n) = match count {
+            1 => ('\u{2032}', 1),
+            2 => ('\u{2033}', 2),
+            3 => ('\u{2034}', 3),
+            _ => ('\u{2057}', 4),
+        };
+        let atom_type = codepoint_atom_type(codepoint).unwrap_or(AtomType::Alpha);
+        contents.push(symbol!(style_symbol(codepoint, local), atom_type));
+        count -= n;
+    }
+    contents
+}
+
+/// Unicode superscript characters, such as `²`, are treated as a superscript
+/// containing the character they are a superscript of.
+#[cfg_attr(rustfmt, rustfmt_skip)]
+fn unicode_superscript(codepoint: char) -> Option<char> {
+    Some(match codepoint {
+        '\u{B9}'                => '1',
+        '\u{B2}'                => '2',
+        '\u{B3}'                => '3',
+        '\u{2070}'              => '0',
+        '\u{2074}'..='\u{2079}' => char::from(b'4' + (codepoint as u32 - 0x2074) as u8),
+        '\u{207A}'              => '+',
+        '\u{207B}'              => '-',
+        '\u{207C}'              => '=',
+        '\u{207D}'              => '(',
+        '\u{207E}'              => ')',
+        '\u{2071}'              => 'i',
+        '\u{207F}'              => 'n',
         _ => return None,
     })
 }
 
+/// The subscript counterpart of `unicode_superscript`, for characters such as `₂`.
+#[cfg_attr(rustfmt, rustfmt_skip)]
+fn unicode_subscript(codepoint: char) -> Option<char> {
+    Some(match codepoint {
+        '\u{2080}'..='\u{2089}' => char::from(b'0' + (codepoint as u32 - 0x2080) as u8),
+        '\u{208A}'              => '+',
+        '\u{208B}'              => '-',
+        '\u{208C}'              => '=',
+        '\u{208D}'              => '(',
+        '\u{208E}'              => ')',
+        '\u{2090}'              => 'a',
+        '\u{2091}'              => 'e',
+        '\u{2092}'              => 'o',
+        '\u{2093}'              => 'x',
+        '\u{2095}'              => 'h',
+        '\u{2096}'              => 'k',
+        '\u{2097}'              => 'l',
+        '\u{2098}'              => 'm',
+        '\u{2099}'              => 'n',
+        '\u{209A}'              => 'p',
+        '\u{209B}'              => 's',
+        '\u{209C}'              => 't',
+        '\u{1D62}'              => 'i',
+        '\u{2C7C}'              => 'j',
+        _ => return None,
+    })
+}
+
+/// Collect a run of Unicode super- or subscript characters into the
+/// contents of a single script, so that `x²³` is the same as `x^{23}`.
+fn unicode_script(
+    lex: &mut Lexer,
+    local: Style,
+    script: fn(char) -> Option<char>,
+) -> Vec<ParseNode> {
+    let mut contents = Vec::new();
+    while let Token::Symbol(c) = lex.current {
+        let base = match script(c) {
+            Some(base) => base,
+            None => break,
+        };
+        let atom_type = codepoint_atom_type(base).unwrap_or(AtomType::Alpha);
+        contents.push(symbol!(style_symbol(base, local), atom_type));
+        lex.next();
+    }
+    contents
+}
+
 // --------------
 //     TESTS
 // --------------
@@ -528,6 +908,37 @@ mod tests {
         display_errors!(errs);
     }
 
+    #[test]
+    fn font_change_scripts() {
+        use crate::parser::nodes::ParseNode;
+
+        // The argument of a font change is the base of the scripts after it,
+        // instead of the scripts having an empty base.
+        let nodes = parse(r"\mathrm{T}^2").unwrap();
+        match nodes.as_slice() {
+            [ParseNode::Scripts(scripts)] => {
+                assert_eq!(
+                    scripts.base,
+                    Some(Box::new(parse(r"\mathrm{T}").unwrap()[0].clone()))
+                )
+            }
+            _ => panic!("expected a single base with scripts, found {:?}", nodes),
+        }
+
+        let mut errs: Vec<String> = Vec::new();
+        should_equate!(
+            errs,
+            parse,
+            [
+                (r"\mathrm{WT}_1", r"{\mathrm{WT}}_1"),
+                (r"\mathrm{T} ^2", r"\mathrm{T}^2"),
+                (r"\mathrm{T} x", r"\mathrm{T}x")
+            ]
+        );
+        should_differ!(errs, parse, [(r"\mathrm{T}^2", r"\mathrm{T}{}^2")]);
+        display_errors!(errs);
+    }
+
     #[test]
     fn delimited() {
         let mut errs: Vec<String> = Vec::new();
@@ -554,4 +965,489 @@ mod tests {
         );
         display_errors!(errs);
     }
+
+    #[test]
+    fn negation() {
+        let mut errs: Vec<String> = Vec::new();
+        should_pass!(
+            errs,
+            parse,
+            [
+                r"a \not= b",
+                r"\not\sim",
+                r"\cancel{x+1}",
+                r"\xcancel x",
+                r"\cancelto{0}{x}"
+            ]
+        );
+        should_fail!(errs, parse, [r"\not", r"\cancelto{0}"]);
+        should_equate!(
+            errs,
+            parse,
+            [
+                (r"\not=", r"\ne"),
+                (r"\not\in", r"\notin"),
+                (r"\not{=}", r"\ne")
+            ]
+        );
+        should_differ!(errs, parse, [(r"\not\sim", r"\sim")]);
+        display_errors!(errs);
+    }
+
+    #[test]
+    fn boxes() {
+        let mut errs: Vec<String> = Vec::new();
+        should_pass!(
+            errs,
+            parse,
+            [
+                r"\boxed{x = 2}",
+                r"\fbox x",
+                r"\colorbox{yellow}{x^2}",
+                r"\fcolorbox{red}{white}{\frac12}"
+            ]
+        );
+        should_fail!(
+            errs,
+            parse,
+            [r"\boxed", r"\colorbox{nocolor}{x}", r"\fcolorbox{red}{x}"]
+        );
+        should_equate!(errs, parse, [(r"\boxed{x}", r"\fbox x")]);
+        display_errors!(errs);
+    }
+
+    #[test]
+    fn phantoms() {
+        let mut errs: Vec<String> = Vec::new();
+        should_pass!(
+            errs,
+            parse,
+            [
+                r"\phantom{x}",
+                r"\hphantom{\frac12}",
+                r"\vphantom x",
+                r"\smash{y}",
+                r"\smash[t]{\sqrt{y}}",
+                r"\smash[b]{g}",
+                r"\sqrt{\mathstrut a} + \sqrt{\strut b}"
+            ]
+        );
+        should_fail!(errs, parse, [r"\phantom", r"\smash[x]{y}", r"\smash[t]"]);
+        should_equate!(
+            errs,
+            parse,
+            [
+                (r"\mathstrut", r"\vphantom("),
+                (r"\strut", r"\mathstrut"),
+                (r"\phantom x", r"\phantom{x}")
+            ]
+        );
+        should_differ!(
+            errs,
+            parse,
+            [
+                (r"\smash{y}", r"\smash[b]{y}"),
+                (r"\hphantom{x}", r"\vphantom{x}")
+            ]
+        );
+        display_errors!(errs);
+    }
+
+    #[test]
+    fn overlaps() {
+        let mut errs: Vec<String> = Vec::new();
+        should_pass!(
+            errs,
+            parse,
+            [
+                r"\llap{=}",
+                r"\rlap x",
+                r"\sum_{\mathclap{1 \le i \le n}} i",
+                r"\raisebox{0.5em}{x}",
+                r"\raisebox{-3mu}[1em][0.2em]{y}",
+                r"\raise1em{x}",
+                r"\lower 2px x"
+            ]
+        );
+        should_fail!(
+            errs,
+            parse,
+            [
+                r"\llap",
+                r"\raisebox{x}",
+                r"\raisebox{1cm}{x}",
+                r"\raise{x}",
+                r"\lower1em"
+            ]
+        );
+        should_equate!(
+            errs,
+            parse,
+            [
+                (r"\mathclap{x}", r"\clap{x}"),
+                (r"\raise1em x", r"\raisebox{1em}{x}"),
+                (r"\lower1em x", r"\raisebox{-1em}{x}"),
+                (r"\raisebox{18mu}{x}", r"\raisebox{1em}{x}")
+            ]
+        );
+        display_errors!(errs);
+    }
+
+    #[test]
+    fn math_choice() {
+        let mut errs: Vec<String> = Vec::new();
+        should_pass!(
+            errs,
+            parse,
+            [
+                r"\mathchoice{D}{T}{S}{SS}",
+                r"\mathchoice{\sum}{\textstyle\sum}{}{} x",
+                r"x^\mathchoice{D}{T}{S}{SS}"
+            ]
+        );
+        should_fail!(errs, parse, [r"\mathchoice", r"\mathchoice{D}{T}{S}"]);
+        should_equate!(
+            errs,
+            parse,
+            [(r"\mathchoice abcd", r"\mathchoice{a}{b}{c}{d}")]
+        );
+        display_errors!(errs);
+    }
+
+    #[test]
+    fn bold_symbols() {
+        let mut errs: Vec<String> = Vec::new();
+        should_pass!(
+            errs,
+            parse,
+            [
+                r"\boldsymbol{\alpha + \nabla \times \vec{B}}",
+                r"\bm\partial_t",
+                r"\boldsymbol{\vartheta^2}",
+                r"\pmb{\sum}",
+                r"\bm{=}"
+            ]
+        );
+        should_fail!(errs, parse, [r"\boldsymbol", r"\pmb"]);
+        should_equate!(
+            errs,
+            parse,
+            [
+                (r"\bm{\nabla}", r"\boldsymbol\nabla"),
+                (r"\boldsymbol{=}", r"\pmb{=}"),
+                (r"\boldsymbol{a+}", r"\boldsymbol{a}\pmb{+}"),
+                (
+                    r"\boldsymbol{\Gamma 2 x}",
+                    r"\mathbf{\Gamma 2}\mathbf{\mathit{x}}"
+                ),
+                (r"\boldsymbol\nabla", r"\mathbf\nabla")
+            ]
+        );
+        should_differ!(
+            errs,
+            parse,
+            [
+                (r"\boldsymbol{x}", r"\mathbf{x}"),
+                (r"\boldsymbol{x}", r"\pmb{x}"),
+                (r"\boldsymbol{\nabla}", r"\pmb{\nabla}")
+            ]
+        );
+        display_errors!(errs);
+    }
+
+    #[test]
+    fn conventions() {
+        use crate::font::MathStyleConvention;
+        use crate::parser::engine::parse_with_convention;
+        let iso = |tex| parse_with_convention(tex, MathStyleConvention::ISO);
+        let upright = |tex| parse_with_convention(tex, MathStyleConvention::Upright);
+
+        let mut errs: Vec<String> = Vec::new();
+        should_pass!(
+            errs,
+            parse,
+            [r"\upalpha + \Upgamma", r"\mathup{x}", r"\upvarepsilon"]
+        );
+        should_fail!(errs, parse, [r"\upfoo", r"\Up"]);
+        should_equate!(
+            errs,
+            parse,
+            [
+                (r"\upalpha", r"\mathup\alpha"),
+                (r"\Upgamma", r"\Gamma"),
+                (r"\mathup{x}", r"\mathrm{x}")
+            ]
+        );
+        should_differ!(
+            errs,
+            parse,
+            [(r"\upalpha", r"\alpha"), (r"\Upgamma", r"\mathit\Gamma")]
+        );
+        should_equate!(
+            errs,
+            iso,
+            [
+                (r"\upalpha", r"\mathup\alpha"),
+                (r"\Gamma", r"\mathit\Gamma")
+            ]
+        );
+        should_equate!(errs, upright, [(r"x + \alpha", r"\mathup{x + \alpha}")]);
+        should_equate!(
+            errs,
+            upright,
+            [(r"\boldsymbol{x \alpha}", r"\mathbf{x \alpha}")]
+        );
+        display_errors!(errs);
+    }
+
+    #[test]
+    fn primes() {
+        let mut errs: Vec<String> = Vec::new();
+        should_pass!(errs, parse, ["f'", "f''(x)", "f'''_1", "y''''' + y'", "'"]);
+        should_fail!(errs, parse, ["f'^2^3", "f^2'", "f'''^2'", "x^'"]);
+        should_equate!(
+            errs,
+            parse,
+            [
+                ("f'", r"f^\prime"),
+                ("f''", r"f^\dprime"),
+                ("f''''", r"f^\qprime"),
+                ("f'^2", r"f^{\prime 2}"),
+                ("f'_1", r"f_1'"),
+                ("f'_1^2", r"f^{\prime 2}_1"),
+                ("f '' ", "f''"),
+                ("f′", "f'"),
+                ("f″", "f''")
+            ]
+        );
+        should_differ!(errs, parse, [("f''", r"f^{\prime\prime}")]);
+        display_errors!(errs);
+    }
+
+    #[test]
+    fn prescripts() {
+        let mut errs: Vec<String> = Vec::new();
+        should_pass!(
+            errs,
+            parse,
+            [
+                r"{}^{14}_6\mathrm{C}",
+                r"{}_1 X^2",
+                r"x + {}^2",
+                r"\prescript{14}{6}{\mathrm{C}}",
+                r"\prescript{}{}{X}",
+                r"\tensor{R}{^a_b^c_d}",
+                r"\tensor[^{14}_6]{C}{}",
+                r"\tensor{\Gamma}{_{ij}^k}"
+            ]
+        );
+        should_fail!(
+            errs,
+            parse,
+            [
+                r"\prescript{a}{b}",
+                r"\tensor{R}{a}",
+                r"\tensor[^a^b]{R}{}",
+                r"\tensor{R}{^a"
+            ]
+        );
+        should_equate!(
+            errs,
+            parse,
+            [
+                (r"{}^{14}_6\mathrm{C}", r"\prescript{14}{6}{\mathrm{C}}"),
+                (r"{}^a X_b", r"\prescript{a}{}{X_b}"),
+                (r"{}_b X", r"\prescript{}{b}{X}"),
+                (r"\tensor[^a_b]{X}{}", r"{\prescript{a}{b}{X}}"),
+                (r"\tensor{X}{}", r"{X}"),
+                (r"\tensor{T}{^a}", r"{T^a}")
+            ]
+        );
+        should_differ!(
+            errs,
+            parse,
+            [(r"{}^a X", r"{}^a {} X"), (r"\tensor{T}{^a_b}", r"{T^a_b}")]
+        );
+        display_errors!(errs);
+    }
+
+    #[test]
+    fn italic_correction() {
+        let mut errs: Vec<String> = Vec::new();
+        should_pass!(errs, parse, [r"f\/(x)", r"\/", r"\mathit{f\/}"]);
+        should_equate!(errs, parse, [(r"f\/ x", r"f\/x")]);
+        should_differ!(errs, parse, [(r"f\/x", r"fx")]);
+        display_errors!(errs);
+    }
+
+    #[test]
+    fn unicode_input() {
+        let mut errs: Vec<String> = Vec::new();
+        should_pass!(
+            errs,
+            parse,
+            ["α ≤ ∑ x²", "∫₀¹ f", "𝑥 + ℝ", "x⁻¹", "速度 = v", "😀^2"]
+        );
+        should_fail!(errs, parse, ["x²^3", "x₁_2", "\u{301}"]);
+        should_equate!(
+            errs,
+            parse,
+            [
+                ("α ≤ β", r"\alpha \le \beta"),
+                ("∑ x", r"\sum x"),
+                ("x²", "x^2"),
+                ("x²³", "x^{23}"),
+                ("x⁻¹", "x^{-1}"),
+                ("a₁₀", "a_{10}"),
+                ("x₁²", "x_1^2"),
+                ("x²_1", "x^2_1"),
+                ("𝑥", r"\mathit{x}")
+            ]
+        );
+        should_differ!(errs, parse, [("𝐱", "x"), ("x²", "x2")]);
+        display_errors!(errs);
+    }
+
+    #[test]
+    fn text() {
+        let mut errs: Vec<String> = Vec::new();
+        should_pass!(
+            errs,
+            parse,
+            [
+                r"\text{速度}",
+                r"v \text{ if } x > 0",
+                r"\operatorname{sn} x",
+                r"\operatorname*{argmax}_x f"
+            ]
+        );
+        should_fail!(errs, parse, [r"\text{x", r"\operatorname x"]);
+        should_equate!(
+            errs,
+            parse,
+            [
+                (r"\sin x", r"\operatorname{sin} x"),
+                (r"\lim_x", r"\operatorname*{lim}_x")
+            ]
+        );
+        should_differ!(
+            errs,
+            parse,
+            [
+                (r"\text{x}", "x"),
+                (r"\operatorname{lim}", r"\operatorname*{lim}")
+            ]
+        );
+        display_errors!(errs);
+    }
+
+    #[test]
+    fn math_version() {
+        let mut errs: Vec<String> = Vec::new();
+        should_pass!(
+            errs,
+            parse,
+            [
+                r"\boldmath x",
+                r"{\boldmath \sum_i x_i} + y",
+                r"\mathversion{bold} x \mathversion{normal} y"
+            ]
+        );
+        should_fail!(errs, parse, [r"\mathversion{heavy}", r"\mathversion x"]);
+        should_equate!(
+            errs,
+            parse,
+            [
+                (r"\mathversion{bold} x", r"\boldmath x"),
+                (r"\mathversion{normal} x", r"\unboldmath x")
+            ]
+        );
+        should_differ!(errs, parse, [(r"\boldmath x", r"\unboldmath x")]);
+        display_errors!(errs);
+    }
+
+    #[test]
+    fn colors() {
+        let mut errs: Vec<String> = Vec::new();
+        should_pass!(
+            errs,
+            parse,
+            [
+                r"\color{red}{x}",
+                r"\color{#ff0000}{x}",
+                r"\color{#ff000080}{x}",
+                r"\color{transparent}{x}",
+                r"\color[rgb]{1,0,0}{x}",
+                r"\color[RGB]{255, 0, 0}{x}",
+                r"\color[HTML]{FF0000}{x}",
+                r"\color[gray]{0.5}{x}",
+                r"\color{red!50!blue}{x}",
+                r"\color{red!50}{x}",
+                r"\color{red!12.5!blue}{x}",
+                r"\textcolor{red}{x}",
+                r"\definecolor{brand}{HTML}{1A2B3C} \color{brand}{x}"
+            ]
+        );
+        should_fail!(
+            errs,
+            parse,
+            [
+                r"\color{#ff00}{x}",
+                r"\color{nocolor}{x}",
+                r"\color[rgb]{2,0,0}{x}",
+                r"\color[cmyk]{0,0,0,1}{x}",
+                r"\color{red!150!blue}{x}",
+                r"\color{brand}{x}",
+                r"\color{red!1.2.3!blue}{x}",
+                r"\color{#+f+f+f}{x}",
+                r"\definecolor{brand}{HTML}{1A2B3C}^2",
+                r"x \definecolor{brand}{HTML}{1A2B3C} _2"
+            ]
+        );
+        should_equate!(
+            errs,
+            parse,
+            [
+                (r"\color{red}{x}", r"\color{#ff0000}{x}"),
+                (r"\color{red}{x}", r"\color[rgb]{1,0,0}{x}"),
+                (r"\color{red} x", r"\textcolor{red}{x}"),
+                (r"{\color{red} a + b} c", r"{\textcolor{red}{a + b}} c"),
+                (
+                    r"\left(\color{red} a \right)",
+                    r"\left(\textcolor{red}{a}\right)"
+                ),
+                (r"\color{red!100!blue}{x}", r"\color{red}{x}"),
+                (r"\color{red!50.0!blue}{x}", r"\color{red!50!blue}{x}"),
+                (r"x \definecolor{brand}{HTML}{1A2B3C} y", r"x y"),
+                (
+                    r"\definecolor{brand}{RGB}{255,0,0}\color{brand}{x}",
+                    r"\color{red}{x}"
+                )
+            ]
+        );
+        display_errors!(errs);
+    }
+
+    #[test]
+    fn parser_commands() {
+        use crate::error::ParseError;
+        use crate::functions::{get_command, PARSER_COMMAND_NAMES};
+
+        // Commands reported to the font coverage are handled by the parser.
+        // They follow an operator so that `\limits` has something to apply to.
+        for name in PARSER_COMMAND_NAMES {
+            let tex = format!(r"\sum\{}", name);
+            let result = parse(&tex);
+            assert!(
+                !matches!(result, Err(ParseError::UnrecognizedCommand(_))),
+                "\\{} is not handled by the parser",
+                name
+            );
+            assert!(
+                get_command(name).is_none(),
+                "\\{} is in the command table",
+                name
+            );
+        }
+    }
 }
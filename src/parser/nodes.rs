@@ -26,6 +26,19 @@ pub enum ParseNode {
     Stack(Stack),
     Extend(char, Unit),
     Array(Array),
+    Cancel(Cancel),
+    Framed(Framed),
+    Phantom(Phantom),
+    Lap(Lap),
+    Raise(Raise),
+    MathChoice(MathChoice),
+    PoorBold(Vec<ParseNode>),
+    /// The italic correction of the preceding glyph, inserted with `\/`.
+    ItalicCorrection,
+    /// Running text from `\text` or an operator name, set in the text font.
+    Text(String),
+    /// Switches the math font for the rest of the group, as `Style` does for the style.
+    MathVersion(MathVersion),
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -46,6 +59,39 @@ pub struct Scripts {
     pub base: Option<Box<ParseNode>>,
     pub superscript: Option<Vec<ParseNode>>,
     pub subscript: Option<Vec<ParseNode>>,
+    /// Scripts placed to the left of the base, as in `{}^{14}_6\mathrm{C}`
+    /// and `\prescript{14}{6}{\mathrm{C}}`.
+    pub presuperscript: Option<Vec<ParseNode>>,
+    pub presubscript: Option<Vec<ParseNode>>,
+}
+
+impl Scripts {
+    pub fn has_prescripts(&self) -> bool {
+        self.presuperscript.is_some() || self.presubscript.is_some()
+    }
+
+    /// Attach pre-scripts to a node.  If the node already has scripts,
+    /// then the pre-scripts will share its base.
+    pub fn with_prescripts(
+        base: ParseNode,
+        presuperscript: Option<Vec<ParseNode>>,
+        presubscript: Option<Vec<ParseNode>>,
+    ) -> Scripts {
+        match base {
+            ParseNode::Scripts(scripts) if !scripts.has_prescripts() => Scripts {
+                presuperscript,
+                presubscript,
+                ..scripts
+            },
+            base => Scripts {
+                base: Some(Box::new(base)),
+                superscript: None,
+                subscript: None,
+                presuperscript,
+                presubscript,
+            },
+        }
+    }
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -89,6 +135,122 @@ pub struct Color {
     pub inner: Vec<ParseNode>,
 }
 
+/// Strokes drawn over a sub-formula, as done by `\not` and the
+/// commands from the `cancel` package.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cancel {
+    pub kind: CancelKind,
+    pub inner: Vec<ParseNode>,
+    /// The value placed at the tip of the arrow for `\cancelto`.
+    pub label: Option<Vec<ParseNode>>,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CancelKind {
+    /// A solidus centered on the inner relation (`\not`).
+    Negation,
+    /// A stroke from bottom left to top right (`\cancel`).
+    Forward,
+    /// A stroke from top left to bottom right (`\bcancel`).
+    Backward,
+    /// Both strokes crossed (`\xcancel`).
+    Cross,
+    /// A forward stroke ending in an arrow (`\cancelto`).
+    To,
+}
+
+/// Content surrounded by padding, with an optional frame and background.
+/// This is used by `\boxed`, `\fbox`, `\colorbox` and `\fcolorbox`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Framed {
+    pub inner: Vec<ParseNode>,
+    pub frame: FrameColor,
+    pub background: Option<RGBA>,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum FrameColor {
+    /// No frame is drawn, only the padding is kept.
+    None,
+    /// The frame is drawn with the current color.
+    Inherit,
+    Color(RGBA),
+}
+
+/// Content whose dimensions are kept while the content itself is hidden,
+/// or content which is drawn while some of its dimensions are ignored.
+/// This is used by `\phantom`, `\hphantom`, `\vphantom` and `\smash`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Phantom {
+    pub kind: PhantomKind,
+    pub inner: Vec<ParseNode>,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PhantomKind {
+    /// Keeps the width, height and depth (`\phantom`).
+    Full,
+    /// Keeps only the width (`\hphantom`).
+    Horizontal,
+    /// Keeps only the height and depth (`\vphantom`).
+    Vertical,
+    /// The content is drawn, but the height (`top`) and/or the
+    /// depth (`bottom`) are treated as zero (`\smash`).
+    Smash { top: bool, bottom: bool },
+}
+
+/// Content which takes up no horizontal space, and instead overlaps
+/// the surrounding content.  This is used by `\llap`, `\rlap`, `\clap`
+/// and the `\mathllap`, `\mathrlap` and `\mathclap` variants.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Lap {
+    pub kind: LapKind,
+    pub inner: Vec<ParseNode>,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum LapKind {
+    /// The content extends to the left of the current position.
+    Left,
+    /// The content extends to the right of the current position.
+    Right,
+    /// The content is centered on the current position.
+    Center,
+}
+
+/// Content shifted vertically, as done by `\raisebox`, `\raise` and `\lower`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Raise {
+    /// The amount to raise the content, negative values lower the content.
+    pub shift: Unit,
+    /// The height and depth reported for the box, which may be
+    /// given with `\raisebox{shift}[height][depth]{..}`.
+    pub height: Option<Unit>,
+    pub depth: Option<Unit>,
+    pub inner: Vec<ParseNode>,
+}
+
+/// The four branches of `\mathchoice{D}{T}{S}{SS}`.  Only the branch which
+/// matches the style is laid out, and is only known at layout time.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MathChoice {
+    pub display: Vec<ParseNode>,
+    pub text: Vec<ParseNode>,
+    pub script: Vec<ParseNode>,
+    pub script_script: Vec<ParseNode>,
+}
+
+impl MathChoice {
+    pub fn branch(&self, style: Style) -> &[ParseNode] {
+        match style {
+            Style::Display | Style::DisplayCramped => &self.display,
+            Style::Text | Style::TextCramped => &self.text,
+            Style::Script | Style::ScriptCramped => &self.script,
+            Style::ScriptScript | Style::ScriptScriptCramped => &self.script_script,
+        }
+    }
+}
+
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub enum BarThickness {
     Default,
@@ -96,6 +258,13 @@ pub enum BarThickness {
     Unit(Unit),
 }
 
+/// The math fonts selected with `\mathversion`, `\boldmath` and `\unboldmath`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum MathVersion {
+    Normal,
+    Bold,
+}
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum MathStyle {
     Display,
@@ -195,6 +364,23 @@ impl ParseNode {
             ParseNode::Extend(_, _) => AtomType::Inner,
             ParseNode::Array(_) => AtomType::Inner,
             ParseNode::Stack(ref s) => s.atom_type,
+            ParseNode::Framed(_) => AtomType::Alpha,
+            ParseNode::Phantom(_) => AtomType::Alpha,
+            ParseNode::Lap(_) => AtomType::Alpha,
+            ParseNode::Raise(_) => AtomType::Alpha,
+            ParseNode::MathChoice(_) => AtomType::Alpha,
+            ParseNode::ItalicCorrection => AtomType::Transparent,
+            ParseNode::Text(_) => AtomType::Alpha,
+            ParseNode::MathVersion(_) => AtomType::Transparent,
+            ParseNode::PoorBold(ref inner) => inner
+                .first()
+                .map(|first| first.atom_type())
+                .unwrap_or(AtomType::Alpha),
+            ParseNode::Cancel(ref cancel) => cancel
+                .inner
+                .first()
+                .map(|first| first.atom_type())
+                .unwrap_or(AtomType::Alpha),
         }
     }
 }
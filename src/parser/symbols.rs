@@ -9,6 +9,103 @@ impl Symbol {
     pub fn from_name(name: &str) -> Option<Self> {
         others(name).or_else(|| symbol(name))
     }
+
+    /// Every name recognized by `Symbol::from_name`, possibly with duplicates.
+    pub fn names() -> impl Iterator<Item = &'static str> {
+        OTHER_NAMES
+            .iter()
+            .copied()
+            .chain(SYMBOLS.iter().map(|sym| sym.name))
+    }
+
+    /// Find the Greek letter of an `upgreek` command, such as `\upalpha` or
+    /// `\Upgamma`.  These letters are expected to be styled upright.
+    pub fn from_upgreek_name(name: &str) -> Option<Self> {
+        let sym = match name.strip_prefix("Up") {
+            Some(rest) => {
+                let mut chars = rest.chars();
+                let capital: String = chars.next()?.to_uppercase().chain(chars).collect();
+                Symbol::from_name(&capital)?
+            }
+            None => Symbol::from_name(name.strip_prefix("up")?)?,
+        };
+
+        match sym.codepoint {
+            '\u{391}'..='\u{3F6}' => Some(sym),
+            _ => None,
+        }
+    }
+
+    /// Find the symbol of a raw Unicode character from the input, such as `≤`.
+    /// Accents are excluded, since they have no nucleus to be placed over.
+    pub fn from_codepoint(codepoint: char) -> Option<Self> {
+        SYMBOLS
+            .iter()
+            .find(|sym| sym.codepoint == codepoint)
+            .filter(|sym| match sym.atom_type {
+                AtomType::Accent | AtomType::Over | AtomType::Under => false,
+                _ => true,
+            })
+            .map(|sym| Symbol {
+                codepoint: sym.codepoint,
+                atom_type: sym.atom_type,
+            })
+    }
+
+    /// Find the precomposed negation of this symbol, as used by `\not`.
+    /// For example `=` will become `≠`.
+    pub fn negated(self) -> Option<Self> {
+        negated_codepoint(self.codepoint).map(|codepoint| Symbol {
+            codepoint,
+            atom_type: self.atom_type,
+        })
+    }
+}
+
+// These are the canonical decompositions `<base> U+0338` found in Unicode.
+#[cfg_attr(rustfmt, rustfmt_skip)]
+fn negated_codepoint(codepoint: char) -> Option<char> {
+    Some(match codepoint {
+        '='        => '\u{2260}', // ≠
+        '<'        => '\u{226E}', // ≮
+        '>'        => '\u{226F}', // ≯
+        '\u{2203}' => '\u{2204}', // ∃ -> ∄
+        '\u{2208}' => '\u{2209}', // ∈ -> ∉
+        '\u{220B}' => '\u{220C}', // ∋ -> ∌
+        '\u{2223}' => '\u{2224}', // ∣ -> ∤
+        '\u{2225}' => '\u{2226}', // ∥ -> ∦
+        '\u{223C}' => '\u{2241}', // ∼ -> ≁
+        '\u{2243}' => '\u{2244}', // ≃ -> ≄
+        '\u{2245}' => '\u{2247}', // ≅ -> ≇
+        '\u{2248}' => '\u{2249}', // ≈ -> ≉
+        '\u{224D}' => '\u{226D}', // ≍ -> ≭
+        '\u{2261}' => '\u{2262}', // ≡ -> ≢
+        '\u{2264}' => '\u{2270}', // ≤ -> ≰
+        '\u{2265}' => '\u{2271}', // ≥ -> ≱
+        '\u{2272}' => '\u{2274}', // ≲ -> ≴
+        '\u{2273}' => '\u{2275}', // ≳ -> ≵
+        '\u{2276}' => '\u{2278}', // ≶ -> ≸
+        '\u{2277}' => '\u{2279}', // ≷ -> ≹
+        '\u{227A}' => '\u{2280}', // ≺ -> ⊀
+        '\u{227B}' => '\u{2281}', // ≻ -> ⊁
+        '\u{227C}' => '\u{22E0}', // ≼ -> ⋠
+        '\u{227D}' => '\u{22E1}', // ≽ -> ⋡
+        '\u{2282}' => '\u{2284}', // ⊂ -> ⊄
+        '\u{2283}' => '\u{2285}', // ⊃ -> ⊅
+        '\u{2286}' => '\u{2288}', // ⊆ -> ⊈
+        '\u{2287}' => '\u{2289}', // ⊇ -> ⊉
+        '\u{2291}' => '\u{22E2}', // ⊑ -> ⋢
+        '\u{2292}' => '\u{22E3}', // ⊒ -> ⋣
+        '\u{22A2}' => '\u{22AC}', // ⊢ -> ⊬
+        '\u{22A8}' => '\u{22AD}', // ⊨ -> ⊭
+        '\u{22A9}' => '\u{22AE}', // ⊩ -> ⊮
+        '\u{22AB}' => '\u{22AF}', // ⊫ -> ⊯
+        '\u{22B2}' => '\u{22EA}', // ⊲ -> ⋪
+        '\u{22B3}' => '\u{22EB}', // ⊳ -> ⋫
+        '\u{22B4}' => '\u{22EC}', // ⊴ -> ⋬
+        '\u{22B5}' => '\u{22ED}', // ⊵ -> ⋭
+        _ => return None,
+    })
 }
 
 fn symbol(name: &str) -> Option<Symbol> {
@@ -21,8 +118,8 @@ fn symbol(name: &str) -> Option<Symbol> {
         })
 }
 
-fn others(name: &str) -> Option<Symbol> {
-    let sym = match name {
+name_table! {
+    fn others(name) -> Symbol, OTHER_NAMES {
         // Additional commands from TeX
         "Alpha" => Symbol {
             codepoint: '\u{391}',
@@ -336,7 +433,5 @@ fn others(name: &str) -> Option<Symbol> {
             atom_type: AtomType::Fence,
         }, // 8214
 
-        _ => return None,
-    };
-    Some(sym)
+    }
 }
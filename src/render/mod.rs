@@ -1,11 +1,17 @@
+use log::warn;
+
 use crate::dimensions::*;
 use crate::error::Error;
 pub use crate::font::MathFont;
-use crate::layout::{Alignment, Grid, Layout, LayoutNode, LayoutSettings, LayoutVariant};
+use crate::font::MathStyleConvention;
+use crate::layout::{Alignment, Grid, Layout, LayoutNode, LayoutSettings, LayoutVariant, Stroke};
 pub use crate::parser::color::RGBA;
 
 pub struct Renderer {
     pub debug: bool,
+    /// Which letters are italic by default, as applied by `Renderer::layout`
+    /// while parsing.  See `parse_with_convention`.
+    pub convention: MathStyleConvention,
 }
 
 #[derive(Copy, Clone, Default)]
@@ -51,6 +57,45 @@ pub trait Backend {
     fn bbox(&mut self, _pos: Cursor, _width: f64, _height: f64, _role: Role) {}
     fn symbol(&mut self, pos: Cursor, gid: u16, scale: f64, ctx: &MathFont);
     fn rule(&mut self, pos: Cursor, width: f64, height: f64);
+    /// Draws a straight line, as used by `\not` and `\cancel`.  By default
+    /// horizontal and vertical lines are drawn as rules.  Other lines are
+    /// approximated by a run of square rules along the line, so backends which
+    /// can stroke paths should draw them instead.
+    fn line(&mut self, from: Cursor, to: Cursor, thickness: f64) {
+        let half = thickness * 0.5;
+        if from.y == to.y {
+            let pos = Cursor {
+                x: from.x.min(to.x),
+                y: from.y - half,
+            };
+            self.rule(pos, (to.x - from.x).abs(), thickness);
+        } else if from.x == to.x {
+            let pos = Cursor {
+                x: from.x - half,
+                y: from.y.min(to.y),
+            };
+            self.rule(pos, thickness, (to.y - from.y).abs());
+        } else if thickness > 0.0 {
+            warn!("approximating a diagonal line with rules, as the backend does not draw lines");
+            let (dx, dy) = (to.x - from.x, to.y - from.y);
+            let steps = (dx.hypot(dy) / thickness).ceil() as usize;
+            for step in 0..=steps {
+                let t = step as f64 / steps as f64;
+                let pos = Cursor {
+                    x: from.x + dx * t - half,
+                    y: from.y + dy * t - half,
+                };
+                self.rule(pos, thickness, thickness);
+            }
+        }
+    }
+    /// Fills the box behind `\colorbox` and `\fcolorbox`.  By default this
+    /// is a rule in the background color.
+    fn background(&mut self, pos: Cursor, width: f64, height: f64, color: RGBA) {
+        self.begin_color(color);
+        self.rule(pos, width, height);
+        self.end_color();
+    }
     fn begin_color(&mut self, color: RGBA);
     fn end_color(&mut self);
 }
@@ -63,7 +108,10 @@ pub enum Role {
 
 impl Renderer {
     pub fn new() -> Self {
-        Renderer { debug: false }
+        Renderer {
+            debug: false,
+            convention: MathStyleConvention::default(),
+        }
     }
     pub fn layout<'s, 'a, 'f>(
         &self,
@@ -71,9 +119,9 @@ impl Renderer {
         layout_settings: LayoutSettings<'a, 'f>,
     ) -> Result<Layout<'f>, Error<'s>> {
         use crate::layout::engine::layout;
-        use crate::parser::parse;
+        use crate::parser::parse_with_convention;
 
-        let mut parse = parse(tex)?;
+        let mut parse = parse_with_convention(tex, self.convention)?;
         Ok(layout(&mut parse, layout_settings)?)
     }
     // (x0, y0, x1, y1)
@@ -131,8 +179,10 @@ impl Renderer {
         if self.debug {
             out.bbox(pos.up(height), nodes_width, height, Role::HBox);
         }
-        if let Alignment::Centered(w) = alignment {
-            pos.x += (nodes_width - w / Px) * 0.5;
+        match alignment {
+            Alignment::Centered(w) => pos.x += (nodes_width - w / Px) * 0.5,
+            Alignment::Right(w) => pos.x += nodes_width - w / Px,
+            _ => (),
         }
 
         for node in nodes {
@@ -150,7 +200,7 @@ impl Renderer {
                 }
                 LayoutVariant::HorizontalBox(ref hbox) => self.render_hbox(
                     out,
-                    pos.down(node.height / Px),
+                    pos.down((node.height + hbox.offset) / Px),
                     &hbox.contents,
                     node.height / Px,
                     node.width / Px,
@@ -178,12 +228,25 @@ impl Renderer {
                             Role::Glyph,
                         );
                     }
-                    out.symbol(pos.down(node.height / Px), gly.gid, gly.size / Px, gly.font);
+                    out.symbol(
+                        pos.down(node.height / Px),
+                        gly.gid,
+                        gly.size / Px,
+                        &gly.font,
+                    );
                 }
 
                 LayoutVariant::Color(_) => panic!("Shouldn't have a color in a vertical box???"),
 
-                LayoutVariant::Kern => { /* NOOP */ }
+                LayoutVariant::Stroke(ref stroke) => {
+                    self.render_stroke(out, pos.down(node.height / Px), stroke)
+                }
+
+                LayoutVariant::Background(color) => {
+                    out.background(pos, node.width / Px, (node.height - node.depth) / Px, color)
+                }
+
+                LayoutVariant::Kern | LayoutVariant::Phantom => { /* NOOP */ }
             }
 
             pos.y += node.height / Px;
@@ -201,7 +264,7 @@ impl Renderer {
                         Role::Glyph,
                     );
                 }
-                out.symbol(pos, gly.gid, gly.size / Px, gly.font);
+                out.symbol(pos, gly.gid, gly.size / Px, &gly.font);
             }
 
             LayoutVariant::Rule => {
@@ -223,7 +286,7 @@ impl Renderer {
             LayoutVariant::HorizontalBox(ref hbox) => {
                 self.render_hbox(
                     out,
-                    pos,
+                    pos.down(hbox.offset / Px),
                     &hbox.contents,
                     node.height / Px,
                     node.width / Px,
@@ -247,9 +310,29 @@ impl Renderer {
                 out.end_color();
             }
 
-            LayoutVariant::Kern => { /* NOOP */ }
+            LayoutVariant::Stroke(ref stroke) => self.render_stroke(out, pos, stroke),
+
+            LayoutVariant::Background(color) => out.background(
+                pos.up(node.height / Px),
+                node.width / Px,
+                (node.height - node.depth) / Px,
+                color,
+            ),
+
+            LayoutVariant::Kern | LayoutVariant::Phantom => { /* NOOP */ }
         } // End macth
     }
+
+    /// Strokes are positioned relative to the baseline at `pos`.
+    fn render_stroke(&self, out: &mut impl Backend, pos: Cursor, stroke: &Stroke) {
+        let (x0, y0) = stroke.from;
+        let (x1, y1) = stroke.to;
+        out.line(
+            pos.translate(x0 / Px, -(y0 / Px)),
+            pos.translate(x1 / Px, -(y1 / Px)),
+            stroke.thickness / Px,
+        );
+    }
 }
 
 #[cfg(feature = "export")]
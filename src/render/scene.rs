@@ -4,7 +4,7 @@ use crate::parser::color::RGBA;
 use font;
 use pathfinder_color::ColorU;
 use pathfinder_content::{
-    outline::Outline,
+    outline::{Contour, Outline},
     stroke::{LineCap, LineJoin, OutlineStrokeToFill, StrokeStyle},
 };
 use pathfinder_geometry::{rect::RectF, transform2d::Transform2F, vector::Vector2F};
@@ -80,6 +80,32 @@ impl<'a> Backend for SceneWrapper<'a> {
             self.paint,
         ));
     }
+    fn line(&mut self, from: Cursor, to: Cursor, thickness: f64) {
+        let mut contour = Contour::new();
+        contour.push_endpoint(v_cursor(from));
+        contour.push_endpoint(v_cursor(to));
+        let mut outline = Outline::new();
+        outline.push_contour(contour);
+
+        let style = StrokeStyle {
+            line_cap: LineCap::Round,
+            line_join: LineJoin::Bevel,
+            line_width: thickness as f32,
+        };
+        let mut stroke = OutlineStrokeToFill::new(&outline, style);
+        stroke.offset();
+        let outline = stroke.into_outline().transformed(&self.transform);
+        self.scene
+            .push_draw_path(DrawPath::new(outline, self.paint));
+    }
+    fn background(&mut self, pos: Cursor, width: f64, height: f64, RGBA(r, g, b, a): RGBA) {
+        let paint = self
+            .scene
+            .push_paint(&Paint::from_color(ColorU::new(r, g, b, a)));
+        let outline = Outline::from_rect(RectF::new(v_cursor(pos), v_xy(width, height)));
+        self.scene
+            .push_draw_path(DrawPath::new(outline.transformed(&self.transform), paint));
+    }
     fn begin_color(&mut self, RGBA(r, g, b, a): RGBA) {
         self.color_stack.push(self.paint);
         self.paint = self
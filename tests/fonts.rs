@@ -0,0 +1,851 @@
+use font::OpenTypeFont;
+use rex::dimensions::{Em, Px};
+use rex::font::{dotless_codepoint, FontContext};
+use rex::layout::{engine, LayoutNode, LayoutSettings, LayoutVariant, Style};
+use rex::parser::parse;
+
+// STIX Two Math and XITS Math are checked in with the SVG test output, and
+// DejaVu Math TeX Gyre is a TrueType font with `glyf` outlines.  All of them
+// have `MATH` tables.
+const FONTS: &[&str] = &[
+    "tests/out/rex-xits.otf",
+    "tests/out/rex-xits_old.otf",
+    "tests/out/dejavu-math.ttf",
+];
+
+fn read_font(path: &str) -> Vec<u8> {
+    std::fs::read(path).unwrap_or_else(|e| panic!("failed to read {}: {}", path, e))
+}
+
+fn load_font(path: &str) -> Box<OpenTypeFont> {
+    let data = read_font(path);
+    match font::parse(&data)
+        .expect("failed to parse font")
+        .downcast_box::<OpenTypeFont>()
+    {
+        Ok(font) => font,
+        Err(_) => panic!("{} is not an OpenType font", path),
+    }
+}
+
+fn width(tex: &str, settings: LayoutSettings) -> f64 {
+    let nodes = parse(tex).expect("failed to parse tex");
+    let layout = engine::layout(&nodes, settings).expect("failed to layout tex");
+    layout.width / Px
+}
+
+/// The font of every glyph in the layout, in order.
+fn glyph_fonts(tex: &str, settings: LayoutSettings) -> Vec<*const OpenTypeFont> {
+    fn collect(nodes: &[LayoutNode], out: &mut Vec<*const OpenTypeFont>) {
+        for node in nodes {
+            match node.node {
+                LayoutVariant::Glyph(ref gly) => out.push(&*gly.font),
+                LayoutVariant::HorizontalBox(ref hbox) => collect(&hbox.contents, out),
+                LayoutVariant::VerticalBox(ref vbox) => collect(&vbox.contents, out),
+                _ => (),
+            }
+        }
+    }
+
+    let nodes = parse(tex).expect("failed to parse tex");
+    let layout = engine::layout(&nodes, settings).expect("failed to layout tex");
+    let mut fonts = Vec::new();
+    collect(&layout.contents, &mut fonts);
+    fonts
+}
+
+macro_rules! assert_same_width {
+    ($settings:expr, $left:expr, $right:expr) => {{
+        let left: f64 = $left.iter().map(|tex| width(tex, $settings)).sum();
+        let right: f64 = $right.iter().map(|tex| width(tex, $settings)).sum();
+        assert!(
+            (left - right).abs() < 1e-6,
+            "{:?} has width {}, while {:?} has width {}",
+            $left,
+            left,
+            $right,
+            right
+        );
+    }};
+}
+
+#[test]
+fn script_kerning_uses_edge_glyphs() {
+    for path in FONTS {
+        let font = load_font(path);
+        let ctx = FontContext::new(&font);
+        let settings = LayoutSettings::new(&ctx, 10.0, Style::Text);
+
+        // The superscript is kerned against the last glyph of the base,
+        // just as it would be for a base of a single glyph.  Both sides hold
+        // the pair kerning between the glyphs of the base once.
+        assert_same_width!(
+            settings,
+            [r"\mathrm{1T}^2", r"\mathrm{T}"],
+            [r"\mathrm{1T}", r"\mathrm{T}^2"]
+        );
+        assert_same_width!(
+            settings,
+            [r"\mathrm{1T}_2", r"\mathrm{T}"],
+            [r"\mathrm{1T}", r"\mathrm{T}_2"]
+        );
+        assert_same_width!(
+            settings,
+            [r"\mathrm{1V}^2_2", r"\mathrm{V}"],
+            [r"\mathrm{1V}", r"\mathrm{V}^2_2"]
+        );
+
+        // Only the first glyph of a script is kerned against the base.
+        assert_same_width!(settings, [r"T^{2x}"], [r"T^2", r"{}^x"]);
+        assert_same_width!(settings, [r"P_{1x}"], [r"P_1", r"{}_x"]);
+
+        // Pre-scripts are kerned against the first glyph of the base.
+        assert_same_width!(
+            settings,
+            [r"{}^2\mathrm{T1}", r"\mathrm{T}"],
+            [r"{}^2\mathrm{T}", r"\mathrm{T1}"]
+        );
+    }
+}
+
+#[test]
+fn script_kerning_skips_foreign_glyphs() {
+    use rex::font::TextFont;
+
+    let (math, other) = (load_font(FONTS[0]), load_font(FONTS[1]));
+    let plain = FontContext::new(&math);
+    let fallback = FontContext::new(&other);
+    let missing = ('\u{20}'..='\u{1FFFF}')
+        .find(|&c| plain.glyph(c).is_err() && fallback.glyph(c).is_ok())
+        .expect("the fallback font covers no extra codepoints");
+
+    // Glyphs from a fallback font have no cut-ins in the `MATH` table, so
+    // scripts are set as if the base had no kerning.
+    let ctx = FontContext::new(&math).with_fallback(&*other);
+    let settings = LayoutSettings::new(&ctx, 10.0, Style::Text);
+    let base = format!(r"\text{{{}}}", missing);
+    let (sup, sub, pre) = (
+        format!("{}^2", base),
+        format!("{}_2", base),
+        format!("{{}}^2{}", base),
+    );
+    assert_same_width!(settings, [sup.as_str()], [base.as_str(), r"{}^2"]);
+    assert_same_width!(settings, [sub.as_str()], [base.as_str(), r"{}_2"]);
+    assert_same_width!(settings, [pre.as_str()], [r"{}^2", base.as_str()]);
+
+    // Neither do glyphs from the text font.
+    let text_font = TextFont::new(&*other);
+    let settings = LayoutSettings::new(&plain, 10.0, Style::Text).with_text_font(&text_font);
+    assert_same_width!(settings, [r"\sin^2"], [r"\sin", r"{}^2"]);
+    assert_same_width!(settings, [r"\sin_2"], [r"\sin", r"{}_2"]);
+    assert_same_width!(
+        settings,
+        [r"\operatorname{f}_2"],
+        [r"\operatorname{f}", r"{}_2"]
+    );
+}
+
+#[test]
+fn phantoms() {
+    let font = load_font(FONTS[0]);
+    let ctx = FontContext::new(&font);
+    let settings = LayoutSettings::new(&ctx, 10.0, Style::Text);
+
+    // Phantoms are empty nodes with the dimensions of their content, rather
+    // than kerns which are skipped when looking for the glyphs next to scripts.
+    for tex in [r"\phantom{x^2}", r"\hphantom{x^2}", r"\vphantom{x^2}"] {
+        let nodes = parse(tex).expect("failed to parse tex");
+        let layout = engine::layout(&nodes, settings).expect("failed to layout tex");
+        assert_eq!(layout.contents.len(), 1);
+        let node = &layout.contents[0];
+        assert!(matches!(node.node, LayoutVariant::Phantom), "{:?}", node);
+        assert!(format!("{:?}", node).starts_with("Phantom("));
+    }
+    assert_same_width!(settings, [r"\phantom{x^2}"], [r"x^2"]);
+    assert_same_width!(settings, [r"\hphantom{x^2}"], [r"x^2"]);
+
+    // A vertical phantom has the height and depth of its content, but no width.
+    let layout = |tex: &str| {
+        let nodes = parse(tex).expect("failed to parse tex");
+        engine::layout(&nodes, settings).expect("failed to layout tex")
+    };
+    let (vphantom, content) = (layout(r"\vphantom{x^2}"), layout(r"x^2"));
+    assert_eq!(vphantom.width / Px, 0.0);
+    assert_eq!(vphantom.height / Px, content.height / Px);
+    assert_eq!(vphantom.depth / Px, content.depth / Px);
+}
+
+#[test]
+fn italic_correction_and_pair_kerning() {
+    for path in FONTS {
+        let font = load_font(path);
+        let ctx = FontContext::new(&font);
+        let settings = LayoutSettings::new(&ctx, 10.0, Style::Text);
+
+        // An italic letter followed by an upright symbol receives the same
+        // italic correction as an explicit `\/`, but not before another letter,
+        // a binary operator or a relation.
+        assert_same_width!(settings, [r"f)"], [r"f\/", r")"]);
+        assert_same_width!(settings, [r"f2"], [r"f\/", r"2"]);
+        assert_same_width!(settings, [r"fx"], [r"f", r"x"]);
+        assert_same_width!(settings, [r"f+x"], [r"f{}+x"]);
+        assert_same_width!(settings, [r"f=x"], [r"f{}=x"]);
+
+        // Upright letters use the pair kerning from the font.
+        let kern = ctx.kerning(ctx.glyph('A').unwrap().gid, ctx.glyph('V').unwrap().gid);
+        let joined = width(r"\mathrm{AV}", settings);
+        let apart = width(r"\mathrm{A}", settings) + width(r"\mathrm{V}", settings);
+        match kern.is_zero() {
+            true => assert!((joined - apart).abs() < 1e-6),
+            false => assert!(joined < apart, "AV should be kerned in {}", path),
+        }
+
+        // So do other upright glyphs, not only ASCII letters.
+        let kern = ctx.kerning(
+            ctx.glyph('\u{393}').unwrap().gid,
+            ctx.glyph('.').unwrap().gid,
+        );
+        let joined = width(r"\Gamma.", settings);
+        let apart = width(r"\Gamma", settings) + width(r".", settings);
+        let kern = kern * ctx.units_per_em.inv() / Em * 10.0;
+        assert!((joined - apart - kern).abs() < 1e-6, "{} in {}", kern, path);
+
+        // An explicit italic correction leaves the spacing around it alone.
+        assert_same_width!(settings, [r"f\/+x", r"f"], [r"f+x", r"f\/"]);
+    }
+
+    // Glyphs from a fallback font are not kerned with the pairs of the math font.
+    let (math, other) = (load_font(FONTS[0]), load_font(FONTS[1]));
+    let plain = FontContext::new(&math);
+    let fallback = FontContext::new(&other);
+    let ctx = FontContext::new(&math).with_fallback(&*other);
+    let settings = LayoutSettings::new(&ctx, 10.0, Style::Text);
+    let mut letters = ('\u{80}'..='\u{1FFFF}')
+        .filter(|&c| c.is_alphanumeric() && plain.glyph(c).is_err() && fallback.glyph(c).is_ok());
+    let (first, second) = (letters.next().unwrap(), letters.next().unwrap());
+    let pair = format!("{}{}", first, second);
+    let (first, second) = (first.to_string(), second.to_string());
+    assert_same_width!(settings, [pair.as_str()], [first.as_str(), second.as_str()]);
+}
+
+#[test]
+fn script_alternates() {
+    for path in FONTS {
+        let font = load_font(path);
+        let ctx = FontContext::new(&font);
+        let settings = LayoutSettings::new(&ctx, 10.0, Style::Text);
+        let gid = |tex: &str, settings: LayoutSettings| {
+            let nodes = parse(tex).expect("failed to parse tex");
+            let layout = engine::layout(&nodes, settings).expect("failed to layout tex");
+            match layout.contents[0].node {
+                LayoutVariant::Glyph(ref gly) => gly.gid,
+                _ => panic!("expected a glyph for {}", tex),
+            }
+        };
+
+        let x = ctx.glyph('\u{1D465}').unwrap().gid;
+        assert_eq!(gid("x", settings), x);
+        for (level, style) in [(1, Style::Script), (2, Style::ScriptScript)] {
+            let expected = ctx.script_alternate(x, level).unwrap_or(x);
+            let styled = LayoutSettings { style, ..settings };
+            assert_eq!(gid("x", styled), expected);
+            assert_eq!(gid("x", styled.with_script_alternates(false)), x);
+        }
+    }
+}
+
+#[test]
+fn dotless_accents() {
+    fn gids(nodes: &[LayoutNode], out: &mut Vec<u16>) {
+        for node in nodes {
+            match node.node {
+                LayoutVariant::Glyph(ref gly) => out.push(gly.gid),
+                LayoutVariant::HorizontalBox(ref hbox) => gids(&hbox.contents, out),
+                LayoutVariant::VerticalBox(ref vbox) => gids(&vbox.contents, out),
+                _ => (),
+            }
+        }
+    }
+
+    for path in FONTS {
+        let font = load_font(path);
+        let ctx = FontContext::new(&font);
+        let settings = LayoutSettings::new(&ctx, 10.0, Style::Text);
+
+        // The dot of `i` and `j` is removed under an accent, in every alphabet
+        // with either a `dtls` substitute or a Unicode dotless letter.
+        for (tex, codepoint) in [
+            (r"\hat{i}", '\u{1D456}'),
+            (r"\vec{j}", '\u{1D457}'),
+            (r"\tilde{\mathrm{i}}", 'i'),
+            (r"\bar{\mathbf{j}}", '\u{1D423}'),
+        ] {
+            let dotted = ctx.glyph(codepoint).unwrap().gid;
+            if ctx.substitute(b"dtls", dotted).is_none() && dotless_codepoint(codepoint).is_none() {
+                continue;
+            }
+
+            let nodes = parse(tex).expect("failed to parse tex");
+            let layout = engine::layout(&nodes, settings).expect("failed to layout tex");
+            let mut used = Vec::new();
+            gids(&layout.contents, &mut used);
+            assert!(!used.contains(&dotted), "{} kept its dot in {}", tex, path);
+        }
+    }
+}
+
+#[test]
+fn fallback_fonts() {
+    let (math, fallback) = (load_font(FONTS[0]), load_font(FONTS[1]));
+    let plain = FontContext::new(&math);
+    let other = FontContext::new(&fallback);
+    let ctx = FontContext::new(&math).with_fallback(&*fallback);
+
+    // Find a codepoint which only the fallback font provides.
+    let missing =
+        ('\u{20}'..='\u{1FFFF}').find(|&c| plain.glyph(c).is_err() && other.glyph(c).is_ok());
+    let missing = missing.expect("the fallback font covers no extra codepoints");
+
+    let glyph = ctx.glyph(missing).expect("fallback glyph");
+    assert!(!ctx.is_math_glyph(&glyph));
+    assert!(ctx.is_math_glyph(&ctx.glyph('x').unwrap()));
+
+    // The fallback glyph has the same size in ems as in its own font.
+    let expected = other.glyph(missing).unwrap().advance * other.units_per_em.inv();
+    let advance = glyph.advance * ctx.units_per_em.inv();
+    assert!((advance / Em - expected / Em).abs() < 1e-6);
+}
+
+#[test]
+fn text_font() {
+    use rex::font::TextFont;
+
+    let (math, text) = (load_font(FONTS[0]), load_font(FONTS[1]));
+    let ctx = FontContext::new(&math);
+    let text_font = TextFont::new(&*text);
+    let settings = LayoutSettings::new(&ctx, 10.0, Style::Text);
+
+    // Text and operator names use the math font unless a text font is given.
+    let math_ptr = &*math as *const OpenTypeFont;
+    let text_ptr = &*text as *const OpenTypeFont;
+    for tex in [r"\text{ab}", r"\sin", r"\operatorname{sn}"] {
+        let fonts = glyph_fonts(tex, settings);
+        assert!(fonts.iter().all(|&font| font == math_ptr));
+        let with_text = glyph_fonts(tex, settings.with_text_font(&text_font));
+        assert!(!with_text.is_empty());
+        assert!(with_text.iter().all(|&font| font == text_ptr));
+    }
+
+    // Text in the math font uses the glyphs of the context, whether or not
+    // the math font is also given as the text font.
+    let advance = ctx.glyph('a').unwrap().advance * ctx.units_per_em.inv() / Em * 10.0;
+    let math_text = TextFont::new(&*math);
+    assert!((width(r"\text{a}", settings) - advance).abs() < 1e-6);
+    assert_eq!(
+        width(r"\text{ab}", settings.with_text_font(&math_text)),
+        width(r"\text{ab}", settings)
+    );
+}
+
+#[test]
+fn bold_math_version() {
+    let (math, bold) = (load_font(FONTS[0]), load_font(FONTS[1]));
+    let math_ptr = &*math as *const OpenTypeFont;
+    let bold_ptr = &*bold as *const OpenTypeFont;
+
+    // Without a bold font, the bold version keeps the normal font.
+    let ctx = FontContext::new(&math);
+    let settings = LayoutSettings::new(&ctx, 10.0, Style::Text);
+    assert_eq!(glyph_fonts(r"\boldmath x", settings), [math_ptr]);
+
+    // The switch lasts until the end of the group.
+    let ctx = FontContext::new(&math).with_bold(&*bold);
+    let settings = LayoutSettings::new(&ctx, 10.0, Style::Text);
+    assert_eq!(
+        glyph_fonts(r"{\boldmath x \unboldmath y} z", settings),
+        [bold_ptr, math_ptr, math_ptr]
+    );
+
+    // Operators, delimiters and radicals all use the bold font.
+    let fonts = glyph_fonts(r"\mathversion{bold} \sum \left( \sqrt{x} \right)", settings);
+    assert!(fonts.iter().all(|&font| font == bold_ptr));
+
+    // The switch has no effect on the spacing around it.
+    assert_same_width!(
+        settings,
+        [r"a\boldmath + b", r"\boldmath a"],
+        [r"\boldmath a + b", r"a"]
+    );
+}
+
+#[test]
+fn constants_override() {
+    use rex::dimensions::{Font, Length};
+    use rex::font::ConstantsOverride;
+
+    for path in FONTS {
+        let font = load_font(path);
+
+        // The subscript shift is read from its own entry in the `MATH` table.
+        let ctx = FontContext::new(&font);
+        let shift_down = f64::from(ctx.math.constants.subscript_shift_down.value);
+        let expected = Length::new(shift_down, Font) * ctx.units_per_em.inv();
+        assert!((ctx.constants.subscript_shift_down / Em - expected / Em).abs() < 1e-9);
+
+        // Overridden constants replace those from the font, and the rest are kept.
+        let overrides = ConstantsOverride::new()
+            .axis_height(Length::new(0.5, Em))
+            .delimiter_factor(1.0);
+        let tuned = FontContext::new(&font).with_constants(&overrides);
+        assert_eq!(tuned.constants.axis_height / Em, 0.5);
+        assert_eq!(tuned.constants.delimiter_factor, 1.0);
+        assert_eq!(
+            tuned.constants.fraction_rule_thickness / Em,
+            ctx.constants.fraction_rule_thickness / Em
+        );
+
+        // Layout follows the overridden constants.
+        let settings = LayoutSettings::new(&ctx, 10.0, Style::Text);
+        let raised = LayoutSettings::new(&tuned, 10.0, Style::Text);
+        let height = |tex: &str, settings: LayoutSettings| {
+            let nodes = parse(tex).expect("failed to parse tex");
+            let layout = engine::layout(&nodes, settings).expect("failed to layout tex");
+            layout.height / Px
+        };
+        assert!(height(r"\frac{1}{2}", raised) > height(r"\frac{1}{2}", settings));
+    }
+}
+
+#[test]
+fn shared_glyph_cache() {
+    for path in FONTS {
+        let font = load_font(path);
+        let ctx = FontContext::new(&font);
+        let expected = width(
+            r"\left( \sum_i \hat{x}_i \right)",
+            LayoutSettings::new(&ctx, 10.0, Style::Display),
+        );
+
+        // Contexts and their clones are shared between threads, and the cached
+        // metrics give the same layout as those computed from the outlines.
+        std::thread::scope(|scope| {
+            for _ in 0..4 {
+                let ctx = ctx.clone();
+                scope.spawn(move || {
+                    let settings = LayoutSettings::new(&ctx, 10.0, Style::Display);
+                    for _ in 0..8 {
+                        let width = width(r"\left( \sum_i \hat{x}_i \right)", settings);
+                        assert_eq!(width, expected);
+                    }
+                });
+            }
+        });
+    }
+}
+
+#[test]
+fn variant_cache() {
+    use rex::dimensions::{Font, Length};
+    use rex::font::VariantGlyph;
+
+    fn parts(variant: VariantGlyph) -> (u16, usize) {
+        match variant {
+            VariantGlyph::Replacement(gid) => (gid, 0),
+            VariantGlyph::Constructable(_, ref parts) => (0, parts.len()),
+        }
+    }
+
+    let font = load_font(FONTS[0]);
+    let ctx = FontContext::new(&font);
+
+    // Asking for more sizes than the cache holds gives the same variants as
+    // a context which has cached none of them.
+    for pass in 0..2 {
+        for size in (0..6000).step_by(3) {
+            let size = Length::new(size as f64, Font);
+            let fresh = FontContext::new(&font);
+            let vert = ctx.vert_variant('(', size).unwrap();
+            let horz = ctx.horz_variant('\u{2192}', size).unwrap();
+            assert_eq!(
+                parts(vert),
+                parts(fresh.vert_variant('(', size).unwrap()),
+                "pass {}",
+                pass
+            );
+            assert_eq!(
+                parts(horz),
+                parts(fresh.horz_variant('\u{2192}', size).unwrap()),
+                "pass {}",
+                pass
+            );
+        }
+    }
+
+    // Fallback glyphs are cached as well, and match those computed afresh.
+    let other = load_font(FONTS[1]);
+    let ctx = FontContext::new(&font).with_fallback(&*other);
+    let missing = ('\u{20}'..='\u{1FFFF}')
+        .find(|&c| ctx.glyph(c).is_ok_and(|glyph| !ctx.is_math_glyph(&glyph)))
+        .expect("the fallback font covers no extra codepoints");
+    let fresh = FontContext::new(&font).with_fallback(&*other);
+    for _ in 0..2 {
+        let (glyph, expected) = (ctx.glyph(missing).unwrap(), fresh.glyph(missing).unwrap());
+        assert_eq!((glyph.gid, glyph.advance), (expected.gid, expected.advance));
+        assert!(glyph.font.ptr_eq(&expected.font));
+    }
+}
+
+#[test]
+fn shared_fonts() {
+    use rex::layout::Layout;
+    use std::sync::Arc;
+
+    fn assert_shareable<T: Send + Sync + 'static>(_: &T) {}
+
+    for path in FONTS {
+        let font: Arc<OpenTypeFont> = Arc::from(load_font(path));
+        let borrowed = FontContext::new(&font);
+        let expected = width(
+            r"\sqrt{x^2 + 1}",
+            LayoutSettings::new(&borrowed, 10.0, Style::Text),
+        );
+
+        // Both contexts expose the `MATH` table of the font.
+        let shared = FontContext::shared(font.clone());
+        let table = font.math.as_ref().unwrap();
+        assert!(std::ptr::eq(&*borrowed.math, table));
+        assert!(std::ptr::eq(&*shared.math, table));
+
+        // Layouts from a shared font outlive the context, and may be sent to
+        // other threads.
+        let layout: Layout<'static> = {
+            let ctx = shared;
+            let nodes = parse(r"\sqrt{x^2 + 1}").expect("failed to parse tex");
+            let settings = LayoutSettings::new(&ctx, 10.0, Style::Text);
+            engine::layout(&nodes, settings).expect("failed to layout tex")
+        };
+        assert_shareable(&layout);
+
+        let width = std::thread::spawn(move || layout.width / Px)
+            .join()
+            .unwrap();
+        assert_eq!(width, expected);
+    }
+}
+
+#[test]
+fn coverage() {
+    use rex::font::{Family, Weight};
+
+    for path in FONTS {
+        let font = load_font(path);
+        let ctx = FontContext::new(&font);
+        let coverage = ctx.coverage();
+
+        for name in &["frac", "binom", "sqrt", "sin", "text"] {
+            assert!(coverage.commands.contains(name), "missing \\{}", name);
+        }
+        for name in &["alpha", "sum", "int", "langle", "Gamma"] {
+            assert!(coverage.symbols.contains(name), "missing \\{}", name);
+        }
+        assert!(coverage.alphabets.contains(&(Family::Normal, Weight::None)));
+        assert!(coverage
+            .alphabets
+            .contains(&(Family::Fraktur, Weight::None)));
+
+        assert!(ctx.supports_command("frac"));
+        assert!(!ctx.supports_command("notacommand"));
+
+        // Commands handled by the parser are reported as well.
+        for name in &[
+            "color",
+            "definecolor",
+            "mathbf",
+            "boldsymbol",
+            "left",
+            "right",
+        ] {
+            assert!(coverage.commands.contains(name), "missing \\{}", name);
+            assert!(ctx.supports_command(name), "missing \\{}", name);
+        }
+        assert!(!ctx.supports_symbol("notasymbol"));
+        assert!(coverage
+            .missing_symbols
+            .iter()
+            .all(|name| !ctx.supports_symbol(name)));
+    }
+}
+
+#[test]
+fn coverage_with_text_font() {
+    use rex::font::TextFont;
+
+    let (math, text) = (load_font(FONTS[0]), load_font(FONTS[1]));
+    let ctx = FontContext::new(&math);
+    let text_font = TextFont::new(&*text);
+
+    // Operator names are checked against the text font when one is given,
+    // and `\not` against the solidus it overlays.
+    assert!(ctx.supports_command_with_text_font("sin", &text_font));
+    assert!(ctx.supports_command_with_text_font("color", &text_font));
+    assert!(ctx.supports_command("not"));
+    assert_eq!(
+        ctx.coverage_with_text_font(&text_font).commands,
+        ctx.coverage().commands
+    );
+}
+
+/// Packs fonts into a collection, as found in `.ttc` files.  The tables of
+/// each font are left in place, and their offsets moved to the collection.
+fn collection(fonts: &[Vec<u8>]) -> Vec<u8> {
+    let mut data = b"ttcf\x00\x01\x00\x00".to_vec();
+    data.extend_from_slice(&(fonts.len() as u32).to_be_bytes());
+    data.resize(12 + 4 * fonts.len(), 0);
+
+    for (i, font) in fonts.iter().enumerate() {
+        while data.len() % 4 != 0 {
+            data.push(0);
+        }
+        let start = data.len();
+        data[12 + 4 * i..16 + 4 * i].copy_from_slice(&(start as u32).to_be_bytes());
+        data.extend_from_slice(font);
+
+        let num_tables = u16::from_be_bytes([font[4], font[5]]) as usize;
+        for table in 0..num_tables {
+            let pos = start + 12 + 16 * table + 8;
+            let offset =
+                u32::from_be_bytes([data[pos], data[pos + 1], data[pos + 2], data[pos + 3]]);
+            data[pos..pos + 4].copy_from_slice(&(offset + start as u32).to_be_bytes());
+        }
+    }
+    data
+}
+
+#[test]
+fn font_collections() {
+    use rex::error::FontError;
+    use rex::font::{face_names, load_math_font, Face};
+
+    let fonts: Vec<Vec<u8>> = FONTS.iter().map(|path| read_font(path)).collect();
+    let data = collection(&fonts);
+
+    let names = face_names(&data).unwrap();
+    assert_eq!(names.len(), fonts.len());
+    assert_eq!(names[0], face_names(&fonts[0]).unwrap()[0]);
+
+    for (index, font) in fonts.iter().enumerate() {
+        let expected = load_math_font(font, Face::Index(0)).unwrap();
+        let by_index = load_math_font(&data, Face::Index(index)).unwrap();
+        let by_name = load_math_font(&data, Face::Name(&names[index])).unwrap();
+
+        let tex = r"\sqrt{\frac{a}{b}} + \int_0^1 x^2";
+        let expected = FontContext::new(&expected);
+        let expected = width(tex, LayoutSettings::new(&expected, 10.0, Style::Display));
+        for font in &[by_index, by_name] {
+            let ctx = FontContext::new(font);
+            assert_eq!(
+                width(tex, LayoutSettings::new(&ctx, 10.0, Style::Display)),
+                expected
+            );
+        }
+    }
+
+    assert_eq!(
+        load_math_font(&data, Face::Index(fonts.len())).err(),
+        Some(FontError::MissingFace(fonts.len()))
+    );
+    assert_eq!(
+        load_math_font(&fonts[0], Face::Index(1)).err(),
+        Some(FontError::MissingFace(1))
+    );
+    assert!(load_math_font(&data, Face::Name("No Such Font")).is_err());
+}
+
+/// Packs a font as WOFF2.  The tables are stored with the null transform, in
+/// uncompressed Brotli meta-blocks.
+fn woff2(font: &[u8]) -> Vec<u8> {
+    fn base128(out: &mut Vec<u8>, value: u32) {
+        let mut groups = vec![(value & 0x7f) as u8];
+        let mut rest = value >> 7;
+        while rest != 0 {
+            groups.push((rest & 0x7f) as u8 | 0x80);
+            rest >>= 7;
+        }
+        out.extend(groups.iter().rev());
+    }
+
+    let read_u32 =
+        |pos: usize| u32::from_be_bytes([font[pos], font[pos + 1], font[pos + 2], font[pos + 3]]);
+    let num_tables = u16::from_be_bytes([font[4], font[5]]) as usize;
+    let mut directory = Vec::new();
+    let mut tables = Vec::new();
+    let mut sfnt_size = 12 + 16 * num_tables;
+    for table in 0..num_tables {
+        let record = 12 + 16 * table;
+        let (start, len) = (
+            read_u32(record + 8) as usize,
+            read_u32(record + 12) as usize,
+        );
+        // The tag is given in full rather than by its index in the known tags.
+        // Only `glyf` and `loca` are transformed by default, so their null
+        // transform is version 3.
+        let tag = &font[record..record + 4];
+        let version = if tag == b"glyf" || tag == b"loca" {
+            3
+        } else {
+            0
+        };
+        directory.push((version << 6) | 63);
+        directory.extend_from_slice(tag);
+        base128(&mut directory, len as u32);
+        tables.extend_from_slice(&font[start..start + len]);
+        sfnt_size += (len + 3) & !3;
+    }
+
+    // The stream begins with a window of 16 bits, which takes a single bit,
+    // and each meta-block has a header of ISLAST, MNIBBLES, MLEN - 1 and
+    // ISUNCOMPRESSED, padded to a byte.  It ends with an empty last block.
+    let mut stream = Vec::new();
+    for (i, block) in tables.chunks(0x10000).enumerate() {
+        let header = ((block.len() as u32 - 1) << 3) | (1 << 19);
+        let header = if i == 0 { header << 1 } else { header };
+        stream.extend_from_slice(&header.to_le_bytes()[..3]);
+        stream.extend_from_slice(block);
+    }
+    stream.push(0b11);
+
+    let mut data = b"wOF2".to_vec();
+    data.extend_from_slice(&font[0..4]);
+    data.extend_from_slice(&[0; 4]);
+    data.extend_from_slice(&(num_tables as u16).to_be_bytes());
+    data.extend_from_slice(&[0; 2]);
+    data.extend_from_slice(&(sfnt_size as u32).to_be_bytes());
+    data.extend_from_slice(&(stream.len() as u32).to_be_bytes());
+    data.extend_from_slice(&[0, 1, 0, 0]);
+    // There is no metadata or private data.
+    data.extend_from_slice(&[0; 20]);
+    data.extend_from_slice(&directory);
+    data.extend_from_slice(&stream);
+    while data.len() % 4 != 0 {
+        data.push(0);
+    }
+    let length = data.len() as u32;
+    data[8..12].copy_from_slice(&length.to_be_bytes());
+    data
+}
+
+#[test]
+fn woff2_fonts() {
+    use rex::error::FontError;
+    use rex::font::{load_math_font, Face};
+
+    let tex = r"\sqrt{\frac{a}{b}} + \int_0^1 x^2";
+    for path in FONTS {
+        let data = read_font(path);
+        let expected = load_math_font(&data, Face::Index(0)).unwrap();
+        let expected = FontContext::new(&expected);
+        let expected = width(tex, LayoutSettings::new(&expected, 10.0, Style::Display));
+
+        let packed = woff2(&data);
+        assert!(packed.starts_with(b"wOF2"));
+        let font = load_math_font(&packed, Face::Index(0)).expect("failed to load WOFF2");
+        let ctx = FontContext::new(&font);
+        assert_eq!(
+            width(tex, LayoutSettings::new(&ctx, 10.0, Style::Display)),
+            expected
+        );
+        assert_eq!(
+            load_math_font(&packed, Face::Index(1)).err(),
+            Some(FontError::MissingFace(1))
+        );
+    }
+}
+
+#[test]
+fn variable_fonts() {
+    use rex::error::FontError;
+    use rex::font::{load_math_font, Face};
+
+    // A font becomes variable when the `post` table is renamed to `fvar`.
+    let mut data = read_font(FONTS[0]);
+    let num_tables = u16::from_be_bytes([data[4], data[5]]) as usize;
+    let record = (0..num_tables)
+        .map(|table| 12 + 16 * table)
+        .find(|&record| &data[record..record + 4] == b"post")
+        .expect("the font has no post table");
+    data[record..record + 4].copy_from_slice(b"fvar");
+
+    // Instances cannot be selected, so variable fonts are rejected in every
+    // format rather than loaded with their default instance.
+    let variable = Some(FontError::VariableFont);
+    assert_eq!(load_math_font(&data, Face::Index(0)).err(), variable);
+    assert_eq!(
+        load_math_font(&woff2(&data), Face::Index(0)).err(),
+        variable
+    );
+    let fonts = [read_font(FONTS[1]), data];
+    let data = collection(&fonts);
+    assert!(load_math_font(&data, Face::Index(0)).is_ok());
+    assert_eq!(load_math_font(&data, Face::Index(1)).err(), variable);
+}
+
+#[test]
+fn default_backend_lines() {
+    use rex::render::{Backend, Cursor, MathFont, Renderer, RGBA};
+
+    /// A backend which only records the sizes of its rules.
+    struct Rules(Vec<(f64, f64)>);
+    impl Backend for Rules {
+        fn symbol(&mut self, _: Cursor, _: u16, _: f64, _: &MathFont) {}
+        fn rule(&mut self, _: Cursor, width: f64, height: f64) {
+            self.0.push((width, height));
+        }
+        fn begin_color(&mut self, _: RGBA) {}
+        fn end_color(&mut self) {}
+    }
+
+    let font = load_font(FONTS[0]);
+    let ctx = FontContext::new(&font);
+    let settings = LayoutSettings::new(&ctx, 10.0, Style::Text);
+    let rules = |tex: &str| {
+        let nodes = parse(tex).expect("failed to parse tex");
+        let layout = engine::layout(&nodes, settings).expect("failed to layout tex");
+        let mut backend = Rules(Vec::new());
+        Renderer::new().render(&layout, &mut backend);
+        backend.0
+    };
+
+    // Backends without lines still draw the diagonal strokes, as a run of
+    // square rules.
+    assert!(rules("x").is_empty());
+    for tex in [r"\cancel{x}", r"\bcancel{x}", r"\xcancel{x}"] {
+        let rules = rules(tex);
+        assert!(rules.len() > 1, "{} has no strokes", tex);
+        assert!(rules.iter().all(|&(width, height)| width == height));
+    }
+}
+
+#[test]
+fn renderer_convention() {
+    use rex::font::MathStyleConvention;
+    use rex::render::Renderer;
+
+    let font = load_font(FONTS[0]);
+    let ctx = FontContext::new(&font);
+    let settings = LayoutSettings::new(&ctx, 10.0, Style::Text);
+    let gid = |renderer: &Renderer| {
+        let layout = renderer
+            .layout(r"\Gamma", settings)
+            .expect("failed to layout tex");
+        match layout.contents[0].node {
+            LayoutVariant::Glyph(ref gly) => gly.gid,
+            _ => panic!("expected a glyph"),
+        }
+    };
+
+    // The convention is applied while the renderer parses its input.
+    let mut renderer = Renderer::new();
+    assert_eq!(gid(&renderer), ctx.glyph('\u{393}').unwrap().gid);
+    renderer.convention = MathStyleConvention::ISO;
+    assert_eq!(gid(&renderer), ctx.glyph('\u{1D6E4}').unwrap().gid);
+}